@@ -1,17 +1,74 @@
-use std::collections::HashMap;
+use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{Write, BufReader};
-use serde_yaml;
 
-fn generate_disadvantage(src_file: &str, dest_file: &str, name: &str) {
+#[derive(Deserialize)]
+struct IpRange {
+    min: u32,
+    max: u32,
+}
+
+#[derive(Deserialize)]
+struct RawDisadvantage {
+    name: String,
+    description: String,
+    ip: Option<IpRange>,
+    #[serde(default)]
+    fixes: BTreeMap<String, i64>,
+    #[serde(default)]
+    requires: BTreeMap<String, i64>,
+    #[serde(default)]
+    excludes: Vec<String>,
+    #[serde(default)]
+    groups: Vec<String>,
+}
+
+fn attribute_map(attributes: &BTreeMap<String, i64>) -> String {
+    let entries: Vec<String> = attributes
+        .iter()
+        .map(|(attribute, value)| format!("(\"{}\".to_string(), {})", attribute.escape_default(), value))
+        .collect();
+    format!("std::collections::BTreeMap::from([{}])", entries.join(", "))
+}
+
+fn generate_disadvantage(src_file: &str, dest_file: &str, name: &str, structured: bool) {
     let file = File::open(src_file).unwrap();
     let buf_reader = BufReader::new(file);
-    let stuff: Vec<HashMap<String, String>> = serde_yaml::from_reader::<BufReader<File>, Vec<HashMap<String, String>>>(buf_reader).unwrap_or_else(|_| vec![]);
+    // Broken defaults must not silently become an empty catalogue
+    let stuff: Vec<RawDisadvantage> = serde_yaml::from_reader::<BufReader<File>, Vec<RawDisadvantage>>(buf_reader)
+        .unwrap_or_else(|err| panic!("{}: {}", src_file, err));
 
     let mut buffer = String::new();
     buffer += "vec![";
     for stuf in stuff {
-        buffer += &*format!("{} {} name: \"{}\".to_string(), description: \"{}\".to_string() {},", name, "{", stuf.get("name").unwrap().escape_default(), stuf.get("description").unwrap().escape_default(), "}")
+        // The structured fields are only emitted when present and only types with these fields get them
+        let mut fields = format!("name: \"{}\".to_string(), description: \"{}\".to_string()", stuf.name.escape_default(), stuf.description.escape_default());
+        if !structured {
+            buffer += &*format!("{} {} {} {},", name, "{", fields, "}");
+            continue;
+        }
+        if let Some(ip) = stuf.ip {
+            if ip.min > ip.max {
+                panic!("{}: {}: ip min {} is greater than max {}", src_file, stuf.name, ip.min, ip.max);
+            }
+            fields += &*format!(", ip: Some(crate::disadvantage::IpRange {} min: {}, max: {} {})", "{", ip.min, ip.max, "}");
+        }
+        if !stuf.fixes.is_empty() {
+            fields += &*format!(", fixes: {}", attribute_map(&stuf.fixes));
+        }
+        if !stuf.requires.is_empty() {
+            fields += &*format!(", requires: {}", attribute_map(&stuf.requires));
+        }
+        if !stuf.excludes.is_empty() {
+            let excludes: Vec<String> = stuf.excludes.iter().map(|exclude| format!("\"{}\".to_string()", exclude.escape_default())).collect();
+            fields += &*format!(", excludes: vec![{}]", excludes.join(", "));
+        }
+        if !stuf.groups.is_empty() {
+            let groups: Vec<String> = stuf.groups.iter().map(|group| format!("\"{}\".to_string()", group.escape_default())).collect();
+            fields += &*format!(", groups: vec![{}]", groups.join(", "));
+        }
+        buffer += &*format!("{} {} {}, ..Default::default() {},", name, "{", fields, "}")
     }
 
     buffer += "]";
@@ -24,37 +81,123 @@ fn generate_disadvantage(src_file: &str, dest_file: &str, name: &str) {
     }
 }
 
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
+enum RawParticle {
+    Electrons,
+    Protons,
+    Neutrons,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawOperand {
+    Particle(RawParticle),
+    Value(i64),
+}
+
+#[derive(Deserialize, Debug)]
+enum RawComparison {
+    #[serde(rename = "==")]
+    Equal,
+    #[serde(rename = "!=")]
+    NotEqual,
+    #[serde(rename = "<")]
+    Less,
+    #[serde(rename = "<=")]
+    LessEqual,
+    #[serde(rename = ">")]
+    Greater,
+    #[serde(rename = ">=")]
+    GreaterEqual,
+}
+
+#[derive(Deserialize)]
+struct RawCondition {
+    left: RawOperand,
+    op: RawComparison,
+    right: RawOperand,
+}
+
+#[derive(Deserialize)]
+struct RawCost {
+    #[serde(default)]
+    mana: u64,
+    #[serde(default)]
+    colored: BTreeMap<String, u64>,
+}
+
+/// Same shape as `Operation` in src/decay_series/operation.rs
+#[derive(Deserialize)]
+struct RawOperation {
+    display: String,
+    electrons: Option<i64>,
+    protons: Option<i64>,
+    neutrons: Option<i64>,
+    #[serde(default)]
+    preconditions: Vec<RawCondition>,
+    #[serde(default)]
+    categories: Vec<String>,
+    cost: Option<RawCost>,
+    probability: Option<f32>,
+}
+
+fn operand(operand: &RawOperand) -> String {
+    match operand {
+        RawOperand::Particle(particle) => format!("crate::decay_series::classification::Operand::Particle(crate::decay_series::classification::Particle::{:?})", particle),
+        RawOperand::Value(value) => format!("crate::decay_series::classification::Operand::Value({})", value),
+    }
+}
+
 fn generate_decay_series(src_file: &str, dest_file: &str) {
     let file = File::open(src_file).unwrap();
     let buf_reader = BufReader::new(file);
-    let stuff: Vec<HashMap<String, String>> = serde_yaml::from_reader::<BufReader<File>, Vec<HashMap<String, String>>>(buf_reader).unwrap_or_else(|_| vec![]);
+    // Broken defaults must not silently become an empty decay series
+    let stuff: Vec<RawOperation> = serde_yaml::from_reader::<BufReader<File>, Vec<RawOperation>>(buf_reader)
+        .unwrap_or_else(|err| panic!("{}: {}", src_file, err));
 
     let mut buffer = String::new();
     buffer += "vec![";
     for stuf in stuff {
-        let mut electrons = stuf.get("electrons").unwrap().to_owned();
-        let mut protons = stuf.get("protons").unwrap().to_owned();
-        let mut neutrons = stuf.get("neutrons").unwrap().to_owned();
-
-        if electrons == "~" {
-            electrons = String::from("None");
-        } else {
-            electrons = String::from(format!("Some({})", electrons));
+        let particle = |count: Option<i64>| count.map(|count| format!("Some({})", count)).unwrap_or_else(|| String::from("None"));
+        let mut fields = format!(
+            "display: \"{}\".to_string(), electrons: {}, protons: {}, neutrons: {}",
+            stuf.display.escape_default(),
+            particle(stuf.electrons),
+            particle(stuf.protons),
+            particle(stuf.neutrons)
+        );
+        if !stuf.preconditions.is_empty() {
+            let conditions: Vec<String> = stuf.preconditions
+                .iter()
+                .map(|condition| format!(
+                    "crate::decay_series::classification::Condition {} left: {}, op: crate::decay_series::classification::Comparison::{:?}, right: {} {}",
+                    "{", operand(&condition.left), condition.op, operand(&condition.right), "}"
+                ))
+                .collect();
+            fields += &*format!(", preconditions: vec![{}]", conditions.join(", "));
         }
-
-        if protons == "~" {
-            protons = String::from("None");
-        } else {
-            protons = String::from(format!("Some({})", protons));
+        if !stuf.categories.is_empty() {
+            let categories: Vec<String> = stuf.categories.iter().map(|category| format!("\"{}\".to_string()", category.escape_default())).collect();
+            fields += &*format!(", categories: vec![{}]", categories.join(", "));
         }
-
-        if neutrons == "~" {
-            neutrons = String::from("None");
-        } else {
-            neutrons = String::from(format!("Some({})", neutrons));
+        if let Some(cost) = stuf.cost {
+            let colored: Vec<String> = cost.colored
+                .iter()
+                .map(|(dice, amount)| format!("(\"{}\".to_string(), {})", dice.escape_default(), amount))
+                .collect();
+            fields += &*format!(
+                ", cost: Some(crate::decay_series::operation::Cost {} mana: {}, colored: std::collections::BTreeMap::from([{}]) {})",
+                "{", cost.mana, colored.join(", "), "}"
+            );
         }
-
-        buffer += &*format!("Operation {} display: \"{}\".to_string(), electrons: {}, protons: {}, neutrons: {}, {},", "{", stuf.get("display").unwrap().escape_default(), electrons, protons, neutrons, "}")
+        if let Some(probability) = stuf.probability {
+            if !(0.0..=100.0).contains(&probability) {
+                panic!("{}: {}: probability {} is not between 0 and 100", src_file, stuf.display, probability);
+            }
+            fields += &*format!(", probability: Some({:?})", probability);
+        }
+        buffer += &*format!("Operation {} {}, ..Default::default() {},", "{", fields, "}")
     }
 
     buffer += "]";
@@ -68,6 +211,7 @@ fn generate_decay_series(src_file: &str, dest_file: &str) {
 }
 
 fn main() {
-    generate_disadvantage("disadvantage.yaml", "src/default_disadvantage.rs", "Disadvantage");
+    generate_disadvantage("disadvantage.yaml", "src/default_disadvantage.rs", "Disadvantage", true);
+    generate_disadvantage("advantage.yaml", "src/default_advantage.rs", "Advantage", false);
     generate_decay_series("decay_series.yaml", "src/decay_series/default_operation.rs");
 }
\ No newline at end of file
@@ -0,0 +1,43 @@
+use crate::common::{load_yaml, Loadable};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+#[derive(Eq, PartialEq, Ord, PartialOrd, Deserialize, Serialize, Default, Hash, Debug, Clone)]
+pub struct Advantage {
+	name: String,
+	description: String,
+}
+
+impl Loadable<Vec<Advantage>> for Advantage {
+	fn load(file: Option<&str>) -> Vec<Advantage> {
+		load_yaml(file, "advantage.yaml", Advantage::defaults, "Neue Vorteile wurden erzeugt")
+	}
+}
+
+impl Advantage {
+	pub fn defaults() -> Vec<Advantage> {
+		include!("default_advantage.rs")
+	}
+}
+
+impl Display for Advantage {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}\n{}", self.name, self.description)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::advantage::Advantage;
+	use crate::common::Loadable;
+
+	#[test]
+	fn test_broken_file_is_kept() {
+		let path = std::env::temp_dir().join(format!("würfeln-advantage-{}.yaml", std::process::id()));
+		std::fs::write(&path, "- name: [").unwrap();
+		let loaded = Advantage::load(path.to_str());
+		assert_eq!(loaded, Advantage::defaults());
+		assert_eq!(std::fs::read_to_string(&path).unwrap(), "- name: [");
+		std::fs::remove_file(&path).unwrap();
+	}
+}
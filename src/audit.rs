@@ -0,0 +1,372 @@
+use crate::commands::decay;
+use crate::decay_series::{Classification, Operation};
+use crate::dice::colored_dice::{ColoredDices, ColoredReport};
+use crate::dice::crit_dice::CritDices;
+use crate::dice::normal_dice::{self, RollReport};
+use crate::history::{Entry, Kind};
+use crate::repl;
+use crate::rng::{Backend, Seed};
+use crate::spell::{self, DrawnSpell, Spells};
+use crate::table::{self, Table};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fmt::{Display, Formatter};
+
+/// Backend of the audit mode
+pub const BACKEND: Backend = Backend::ChaCha20;
+
+/// How far ahead in the chain a sub-seed is searched, every sub-seed skipped on the way is a missing roll
+const MAX_SEARCHED: usize = 10_000;
+
+/**
+SHA-256 of the seed, shown at the start of an audited session. It binds the seed without revealing it
+ */
+pub fn commitment(seed: &Seed) -> String {
+	let hash = Sha256::digest(format!("würfeln audit {} {}", BACKEND, seed).as_bytes());
+	hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/**
+Everything the rolls depend on besides the seed, the verifier needs the same configuration as the session
+ */
+pub struct Rules<'a> {
+	pub colored_dice: &'a ColoredDices,
+	pub crits: &'a CritDices,
+	pub tables: &'a [Table],
+	pub spells: &'a [Spells],
+	pub operations: &'a [Operation],
+	pub classifications: &'a [Classification],
+}
+
+#[derive(Debug, Serialize)]
+pub struct Failure {
+	/// Position in the log, starting at 1
+	pub number: usize,
+	#[serde(rename = "type")]
+	pub kind: Kind,
+	pub input: String,
+	pub reason: String,
+}
+
+/**
+Result of `würfeln verify`
+ */
+#[derive(Debug, Default, Serialize)]
+pub struct Verification {
+	pub commitment: String,
+	/// Rolls of the audited session
+	pub rolls: usize,
+	/// Rolled again from their sub-seed with the same result
+	pub replayed: usize,
+	/// Only the sub-seed could be checked, e.g. for a decay series from the menu, so the result may have been changed
+	pub unverified: usize,
+	/// Entered from physical dice, there is nothing to check
+	pub physical: usize,
+	/// Rolls of other sessions before and after the audited session
+	pub ignored: usize,
+	pub failures: Vec<Failure>,
+}
+
+impl Verification {
+	pub fn passed(&self) -> bool {
+		self.rolls > 0 && self.failures.is_empty() && self.unverified == 0
+	}
+}
+
+impl Display for Verification {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		writeln!(f, "Commitment: {}", self.commitment)?;
+		writeln!(f, "Würfe der Sitzung: {}", self.rolls)?;
+		writeln!(f, "Nachgewürfelt: {}", self.replayed)?;
+		writeln!(f, "Nicht nachprüfbar: {}", self.unverified)?;
+		writeln!(f, "Echte Würfel: {}", self.physical)?;
+		if self.ignored > 0 {
+			writeln!(f, "Andere Sitzungen: {}", self.ignored)?;
+		}
+		for failure in &self.failures {
+			writeln!(f, "{}. {}: {}: {}", failure.number, failure.kind, failure.input, failure.reason)?;
+		}
+		if self.rolls == 0 {
+			write!(f, "Der Verlauf enthält keine Würfe zu diesem Seed")
+		} else if !self.failures.is_empty() {
+			write!(f, "{} Würfe stimmen nicht", self.failures.len())
+		} else if self.unverified > 0 {
+			write!(f, "{} Würfe lassen sich nicht nachwürfeln, ihr Ergebnis ist nicht geprüft", self.unverified)
+		} else {
+			write!(f, "Kein Wurf wurde verändert")
+		}
+	}
+}
+
+/**
+Draws the spells of an entry again, its input is "<categories> x<amount>" like the spell subcommand and the menu record it
+ */
+fn replay_spells(input: &str, spells: &[Spells], rng: &mut impl rand::Rng) -> Result<Vec<DrawnSpell>, String> {
+	let (names, amount) = input.rsplit_once(" x").ok_or("Die Anzahl fehlt")?;
+	let amount = amount.parse::<usize>().map_err(|err| err.to_string())?;
+	let categories: Vec<&Spells> = match names {
+		"Alle" => spells.iter().collect(),
+		names => names.split(", ")
+			.map(|name| spell::find(spells, name).ok_or_else(|| format!("Unbekannte Kategorie: {}", name)))
+			.collect::<Result<_, _>>()?,
+	};
+	Ok(spell::draw(&categories, amount, rng).iter().map(|(category, spell)| DrawnSpell::new(category, spell)).collect())
+}
+
+/**
+Rolls the entry again with its sub-seed, `None` if the kind can not be rolled again from its input alone
+ */
+fn replay(entry: &Entry, seed: Seed, rules: &Rules) -> Option<Result<serde_json::Value, String>> {
+	let mut rng = seed.replay(BACKEND);
+	let result = match entry.kind {
+		Kind::Roll => {
+			// Only the old style knows every single value
+			let old_style = entry.result.get(0).and_then(|report| report.get("values")).is_some();
+			entry.input
+				.split_whitespace()
+				.map(|expression| {
+					normal_dice::parse_expression(expression)
+						.map(|(amount, sides)| normal_dice::roll(amount, sides, old_style, &mut rng).report())
+				})
+				.collect::<Result<Vec<RollReport>, String>>()
+				.and_then(|reports| serde_json::to_value(reports).map_err(|err| err.to_string()))
+		},
+		Kind::Colored => {
+			let results = match entry.input.parse::<usize>() {
+				Ok(value) => Ok(rules.colored_dice.roll_value(value, &mut rng)),
+				Err(_) => rules.colored_dice
+					.parse_selection(&entry.input)
+					.map(|selection| ColoredDices::roll_selection(&selection, &mut rng)),
+			};
+			results.and_then(|results| serde_json::to_value(ColoredReport::new(&results)).map_err(|err| err.to_string()))
+		},
+		Kind::Crit => entry.input
+			.parse::<i16>()
+			.map_err(|err| err.to_string())
+			.and_then(|value| serde_json::to_value(rules.crits.evaluate(value, &mut rng)).map_err(|err| err.to_string())),
+		Kind::Table => table::find(rules.tables, &entry.input)
+			.ok_or_else(|| format!("Die Tabelle \"{}\" existiert nicht", entry.input))
+			.and_then(|found| found.roll(rules.tables, &mut rng).map_err(|err| err.to_string()))
+			.and_then(|results| serde_json::to_value(results).map_err(|err| err.to_string())),
+		Kind::Spell => replay_spells(&entry.input, rules.spells, &mut rng)
+			.and_then(|drawn| serde_json::to_value(drawn).map_err(|err| err.to_string())),
+		// Only the decay subcommand records its arguments, see decay::record
+		Kind::Decay if entry.input.starts_with("--start") => repl::split(&entry.input)
+			.and_then(|words| {
+				decay::command()
+					.try_get_matches_from(std::iter::once("decay".to_string()).chain(words))
+					.map_err(|err| err.to_string())
+			})
+			.and_then(|matches| decay::decay(&matches, rules.operations, rules.classifications, &mut rng))
+			.and_then(|report| serde_json::to_value(report).map_err(|err| err.to_string())),
+		_ => return None,
+	};
+	Some(result)
+}
+
+enum Outcome {
+	Replayed,
+	Unverified,
+	Physical,
+}
+
+/**
+Position in the chain of sub-seeds. Only recorded rolls use up a sub-seed, so the chain has no gaps
+ */
+struct Chain {
+	expected: Seed,
+	previous: Option<Seed>,
+}
+
+impl Chain {
+	/// Moves to `sub_seed` and returns the sub-seeds which were skipped, each of them is a roll missing in the log
+	fn advance(&mut self, sub_seed: Seed) -> Result<Vec<Seed>, String> {
+		// Several rolls can share a generator, e.g. the operations of one decay
+		if self.previous == Some(sub_seed) {
+			return Ok(Vec::new());
+		}
+		let chain = || std::iter::successors(Some(self.expected), |seed| Some(seed.next(BACKEND)));
+		let position = chain()
+			.take(MAX_SEARCHED)
+			.position(|seed| seed == sub_seed)
+			.ok_or_else(|| format!("Der Sub-Seed {} folgt nicht aus dem Seed", sub_seed))?;
+		let skipped = chain().take(position).collect();
+		self.previous = Some(sub_seed);
+		self.expected = sub_seed.next(BACKEND);
+		Ok(skipped)
+	}
+}
+
+/**
+Checks one roll and adds every reason why it does not pass to `reasons`
+ */
+fn check(entry: &Entry, chain: &mut Chain, rules: &Rules, reasons: &mut Vec<String>) -> Option<Outcome> {
+	if entry.physical {
+		return Some(Outcome::Physical);
+	}
+	let Some(sub_seed) = entry.seed else {
+		reasons.push("Der Wurf hat keinen Sub-Seed".to_string());
+		return None;
+	};
+	match chain.advance(sub_seed) {
+		Ok(skipped) => reasons.extend(skipped.into_iter().map(|seed| format!("Davor fehlt der Wurf mit dem Sub-Seed {}", seed))),
+		Err(reason) => {
+			reasons.push(reason);
+			return None;
+		},
+	}
+
+	match replay(entry, sub_seed, rules) {
+		Some(Ok(result)) if result == entry.result => Some(Outcome::Replayed),
+		Some(Ok(_)) => {
+			reasons.push("Das Ergebnis wurde verändert".to_string());
+			None
+		},
+		Some(Err(err)) => {
+			reasons.push(format!("Nicht nachwürfelbar: {}", err));
+			None
+		},
+		None => Some(Outcome::Unverified),
+	}
+}
+
+/**
+Checks the rolls of the session committed to `seed`. The sub-seeds have to follow from the seed
+one after another in the order of the log and every roll which can be rolled again has to have the same result
+ */
+pub fn verify(entries: &[Entry], seed: Seed, commitment: Option<&str>, rules: &Rules) -> Result<Verification, String> {
+	let expected = self::commitment(&seed);
+	if let Some(commitment) = commitment.filter(|commitment| !commitment.eq_ignore_ascii_case(&expected)) {
+		return Err(format!("Der Seed passt nicht zum Commitment {}, sein Commitment ist {}", commitment, expected));
+	}
+
+	let mut verification = Verification {
+		commitment: expected,
+		..Default::default()
+	};
+	// The seed itself is never handed out, see Seeds::audited
+	let mut chain = Chain {
+		expected: seed.next(BACKEND),
+		previous: None,
+	};
+	let audited = |entry: &Entry| entry.commitment.as_deref() == Some(verification.commitment.as_str());
+	// Nothing else may be written between the first and the last roll of the audited session
+	let span = entries.iter().position(audited).zip(entries.iter().rposition(audited));
+	for (index, entry) in entries.iter().enumerate() {
+		if !span.is_some_and(|(first, last)| (first..=last).contains(&index)) {
+			verification.ignored += 1;
+			continue;
+		}
+		verification.rolls += 1;
+		let mut reasons = Vec::new();
+		let outcome = match audited(entry) {
+			true => check(entry, &mut chain, rules, &mut reasons),
+			false => {
+				reasons.push("Der Wurf gehört nicht zu dieser Sitzung, er hat ein anderes oder kein Commitment".to_string());
+				None
+			},
+		};
+		match outcome {
+			Some(Outcome::Replayed) => verification.replayed += 1,
+			Some(Outcome::Unverified) => verification.unverified += 1,
+			Some(Outcome::Physical) => verification.physical += 1,
+			None => (),
+		}
+		verification.failures.extend(reasons.into_iter().map(|reason| Failure {
+			number: index + 1,
+			kind: entry.kind,
+			input: entry.input.clone(),
+			reason,
+		}));
+	}
+	Ok(verification)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::audit::{commitment, verify, Rules, BACKEND};
+	use crate::dice::colored_dice::ColoredDices;
+	use crate::dice::crit_dice::CritDices;
+	use crate::dice::normal_dice;
+	use crate::history::{Entry, Kind};
+	use crate::rng::Seed;
+	use crate::spell::{self, DrawnSpell, Spells};
+
+	#[test]
+	fn test_verify() {
+		let seed: Seed = "42".parse().unwrap();
+		let sub_seed = seed.next(BACKEND);
+		let report = normal_dice::roll(5, 6, true, &mut sub_seed.replay(BACKEND)).report();
+		let mut entry = Entry {
+			timestamp: 0,
+			kind: Kind::Roll,
+			input: "5d6".to_string(),
+			result: serde_json::to_value([&report]).unwrap(),
+			text: String::new(),
+			seed: Some(sub_seed),
+			player: None,
+			physical: false,
+			session: None,
+			commitment: Some(commitment(&seed)),
+		};
+		let colored_dice = ColoredDices::default();
+		let crits = CritDices::default();
+		let spells = Spells::defaults();
+		let rules = Rules {
+			colored_dice: &colored_dice,
+			crits: &crits,
+			tables: &[],
+			spells: &spells,
+			operations: &[],
+			classifications: &[],
+		};
+
+		let verification = verify(std::slice::from_ref(&entry), seed, Some(&commitment(&seed)), &rules).unwrap();
+		assert!(verification.passed());
+		assert_eq!(verification.replayed, 1);
+		assert!(verify(&[], seed, Some("00"), &rules).is_err());
+
+		// A shared generator keeps the sub-seed, a deleted roll leaves a gap
+		let mut shared = entry.clone();
+		shared.seed = Some(sub_seed);
+		assert!(verify(&[entry.clone(), shared], seed, None, &rules).unwrap().passed());
+		let mut gap = entry.clone();
+		gap.seed = Some(sub_seed.next(BACKEND).next(BACKEND));
+		gap.result = serde_json::to_value([normal_dice::roll(5, 6, true, &mut sub_seed.next(BACKEND).next(BACKEND).replay(BACKEND)).report()]).unwrap();
+		let verification = verify(&[entry.clone(), gap], seed, None, &rules).unwrap();
+		assert_eq!(verification.replayed, 2);
+		assert_eq!(verification.failures.len(), 1);
+		assert_eq!(verification.failures[0].number, 2);
+
+		// Spells are drawn again, what can not be rolled again is not passed
+		let mut drawn = entry.clone();
+		drawn.kind = Kind::Spell;
+		drawn.input = format!("{}, {} x3", spells[0].name, spells[1].name);
+		let categories = [&spells[0], &spells[1]];
+		let spells_drawn: Vec<DrawnSpell> = spell::draw(&categories, 3, &mut sub_seed.replay(BACKEND))
+			.iter()
+			.map(|(category, spell)| DrawnSpell::new(category, spell))
+			.collect();
+		drawn.result = serde_json::to_value(spells_drawn).unwrap();
+		assert_eq!(verify(std::slice::from_ref(&drawn), seed, None, &rules).unwrap().replayed, 1);
+		drawn.kind = Kind::Advantage;
+		let verification = verify(&[drawn], seed, None, &rules).unwrap();
+		assert_eq!(verification.unverified, 1);
+		assert!(!verification.passed());
+
+		// Another session is only fine before or after the audited one
+		let mut foreign = entry.clone();
+		foreign.commitment = None;
+		let verification = verify(&[foreign.clone(), entry.clone(), foreign.clone()], seed, None, &rules).unwrap();
+		assert!(verification.passed());
+		assert_eq!(verification.ignored, 2);
+		assert!(!verify(&[entry.clone(), foreign, entry.clone()], seed, None, &rules).unwrap().passed());
+
+		entry.result[0]["sum"] = serde_json::json!(report.sum + 1);
+		assert!(!verify(std::slice::from_ref(&entry), seed, None, &rules).unwrap().passed());
+		entry.seed = Some(Seed::Short(7));
+		assert!(!verify(std::slice::from_ref(&entry), seed, None, &rules).unwrap().passed());
+		entry.seed = Some(seed);
+		assert!(!verify(&[entry], seed, None, &rules).unwrap().passed());
+	}
+}
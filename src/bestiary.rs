@@ -0,0 +1,98 @@
+use crate::common::{load_yaml, Loadable};
+use crate::decay_series::{Classification, State};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+const BESTIARY_FILE: &str = "bestiary.yaml";
+
+/**
+A monster or plant of the decay series, identified by its category and number
+ */
+#[derive(PartialEq, Deserialize, Serialize, Debug, Clone)]
+pub struct BestiaryEntry {
+	pub category: String,
+	pub number: i64,
+	pub name: String,
+	#[serde(default)]
+	pub description: String,
+	#[serde(default)]
+	pub stats: BTreeMap<String, serde_yaml::Value>,
+}
+
+impl Loadable<Vec<BestiaryEntry>> for BestiaryEntry {
+	/// Unlike the other files the bestiary is written by hand, so it starts empty
+	fn load(file: Option<&str>) -> Vec<BestiaryEntry> {
+		load_yaml(file, BESTIARY_FILE, Vec::new, "Ein leeres Bestiarium wurde erzeugt")
+	}
+}
+
+impl Display for BestiaryEntry {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{} ({} Nr. {})", self.name, self.category, self.number)?;
+		if !self.description.is_empty() {
+			write!(f, "\n{}", self.description)?;
+		}
+		for (stat, value) in &self.stats {
+			let value = match value {
+				serde_yaml::Value::String(text) => text.clone(),
+				serde_yaml::Value::Number(number) => number.to_string(),
+				serde_yaml::Value::Bool(flag) => flag.to_string(),
+				other => serde_yaml::to_string(other).unwrap_or_default().trim().to_string(),
+			};
+			write!(f, "\n{}: {}", stat, value)?;
+		}
+		Ok(())
+	}
+}
+
+pub fn find<'a>(entries: &'a [BestiaryEntry], category: &str, number: i64) -> Option<&'a BestiaryEntry> {
+	entries.iter().find(|entry| entry.category == category && entry.number == number)
+}
+
+/**
+Looks up the entry describing the given state of a decay series
+ */
+pub fn lookup<'a>(entries: &'a [BestiaryEntry], classifications: &[Classification], state: &State) -> Option<&'a BestiaryEntry> {
+	Classification::find(classifications, state)
+		.and_then(|rule| find(entries, &rule.category, rule.number.count(state)))
+}
+
+/**
+All categories which have at least one entry, in order of their first appearance
+ */
+pub fn categories(entries: &[BestiaryEntry]) -> Vec<&str> {
+	let mut categories: Vec<&str> = Vec::new();
+	for entry in entries {
+		if !categories.contains(&&*entry.category) {
+			categories.push(&entry.category);
+		}
+	}
+	categories
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::bestiary::{lookup, BestiaryEntry};
+	use crate::decay_series::{Classification, State};
+	use std::collections::BTreeMap;
+
+	#[test]
+	fn test_lookup() {
+		let entries = vec![BestiaryEntry {
+			category: "Monster".to_string(),
+			number: 7,
+			name: "Sumpfgolem".to_string(),
+			description: String::new(),
+			stats: BTreeMap::new(),
+		}];
+		let rules = Classification::defaults();
+
+		let found = lookup(&entries, &rules, &State { electrons: 7, protons: 7, neutrons: 0 });
+		assert_eq!(found.map(|entry| entry.name.as_str()), Some("Sumpfgolem"));
+		// Classified, but there is no entry for this number
+		assert!(lookup(&entries, &rules, &State { electrons: 8, protons: 8, neutrons: 0 }).is_none());
+		// Not classified at all
+		assert!(lookup(&entries, &rules, &State { electrons: 2, protons: 3, neutrons: 0 }).is_none());
+	}
+}
@@ -71,7 +71,7 @@ mod tests {
 
     #[test]
     #[should_panic]
-    fn color_failing() -> () {
+    fn color_failing() {
         get_color("#F").expect("Color decoding fail ");
     }
 
@@ -0,0 +1,79 @@
+use crate::commands::{Rolled, EXIT_SUCCESS, EXIT_USAGE};
+use crate::dice::colored_dice::{ColoredDice, ColoredDices, ColoredReport};
+use crate::history::{History, Kind};
+use crate::output::{self, Format};
+use crate::{dbgprintln, edbgprintln};
+use clap::{Arg, ArgGroup, ArgMatches, Command};
+use rand::Rng;
+
+pub fn command() -> Command {
+	Command::new("colored")
+		.about("Würfelt farbige Würfel, z.B. 3r2g")
+		.arg(Arg::new("dice")
+			.help("Anzahl und Kürzel der Würfel, z.B. 3r2g")
+		)
+		.arg(Arg::new("value")
+			.long("value")
+			.value_parser(clap::value_parser!(u64))
+			.help("Würfelt Würfel mit diesem Gesamtwert, die wertvollsten zuerst")
+		)
+		.group(ArgGroup::new("selection")
+			.args(["dice", "value"])
+			.required(true)
+		)
+}
+
+/// The dice selected in the matches, none for a value. Fails if the selection contains an unknown dice
+pub fn selection<'a>(matches: &ArgMatches, colored_dice: &'a ColoredDices) -> Result<Vec<(&'a ColoredDice, usize)>, String> {
+	let Some(input) = matches.get_one::<String>("dice") else {
+		return Ok(vec![]);
+	};
+	colored_dice.parse_selection(input).map_err(|err| {
+		let known: Vec<String> = colored_dice.dices.iter().map(|dice| format!("{} ({})", dice.short, dice.long)).collect();
+		format!("{}, bekannt sind: {}", err, known.join(", "))
+	})
+}
+
+/**
+Rolls the selection or the value of the matches, fails if the selection contains an unknown dice
+ */
+pub fn evaluate(matches: &ArgMatches, colored_dice: &ColoredDices, rng: &mut impl Rng) -> Result<Rolled<ColoredReport>, String> {
+	let (input, results): (String, Vec<(&ColoredDice, usize, u64)>) = match matches.get_one::<u64>("value") {
+		Some(value) => (value.to_string(), colored_dice.roll_value(*value as usize, rng)),
+		None => {
+			let input = matches.get_one::<String>("dice").cloned().unwrap_or_default();
+			let selection = selection(matches, colored_dice)?;
+			let results = ColoredDices::roll_selection(&selection, rng);
+			(input, results)
+		},
+	};
+	let report = ColoredReport::new(&results);
+	Ok(Rolled {
+		kind: Kind::Colored,
+		input,
+		text: report.to_string(),
+		report,
+	})
+}
+
+/**
+Runs the subcommand and returns the exit code
+ */
+pub fn run(matches: &ArgMatches, colored_dice: &ColoredDices, format: Format, history: &History, rng: &mut impl Rng) -> i32 {
+	let rolled = match evaluate(matches, colored_dice, rng) {
+		Ok(rolled) => rolled,
+		Err(err) => {
+			edbgprintln!("{}", err);
+			return EXIT_USAGE;
+		},
+	};
+	let report = &rolled.report;
+	output::print(format, report, || {
+		for dice in &report.dice {
+			dbgprintln!("{}: {}", dice.name, dice.result);
+		}
+		dbgprintln!("Insgesamt: {} ({})", report.total, report.value);
+	});
+	rolled.record(history);
+	EXIT_SUCCESS
+}
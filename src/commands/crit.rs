@@ -0,0 +1,39 @@
+use crate::commands::{Rolled, EXIT_SUCCESS};
+use crate::dbgprintln;
+use crate::dice::crit_dice::{CritDices, CritResult};
+use crate::history::{History, Kind};
+use crate::output::{self, Format};
+use clap::{Arg, ArgMatches, Command};
+use rand::Rng;
+
+pub fn command() -> Command {
+	Command::new("crit")
+		.about("Würfelt Crit Würfel")
+		.arg(Arg::new("value")
+			.required(true)
+			.value_parser(clap::value_parser!(i16).range(0..))
+			.help("Gesamtwert der Crit Würfel")
+		)
+}
+
+/// Rolls the crit dice of the value of the matches
+pub fn evaluate(matches: &ArgMatches, crits: &CritDices, rng: &mut impl Rng) -> Rolled<CritResult> {
+	let value = matches.get_one::<i16>("value").copied().unwrap_or_default();
+	let result = crits.evaluate(value, rng);
+	Rolled {
+		kind: Kind::Crit,
+		input: value.to_string(),
+		text: result.to_string(),
+		report: result,
+	}
+}
+
+/**
+Runs the subcommand and returns the exit code
+ */
+pub fn run(matches: &ArgMatches, crits: &CritDices, format: Format, history: &History, rng: &mut impl Rng) -> i32 {
+	let rolled = evaluate(matches, crits, rng);
+	output::print(format, &rolled.report, || dbgprintln!("{}", rolled.text));
+	rolled.record(history);
+	EXIT_SUCCESS
+}
@@ -0,0 +1,145 @@
+use crate::commands::{EXIT_FAILURE, EXIT_SUCCESS, EXIT_USAGE};
+use crate::decay_series::{Classification, Operation, State, Step};
+use crate::history::{History, Kind};
+use crate::output::{self, Format};
+use crate::repl;
+use crate::{dbgprintln, edbgprintln};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/**
+Result of a non-interactive decay series, also used as JSON output and in the history
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DecayReport {
+	pub start: State,
+	pub steps: Vec<Step>,
+	pub state: State,
+	pub description: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+}
+
+pub fn command() -> Command {
+	Command::new("decay")
+		.about("Wendet Operationen der Zerfallsreihe auf einen Zustand an")
+		.arg(Arg::new("start")
+			.long("start")
+			.required(true)
+			.help("Startzustand, z.B. \"10;10;0\" oder \"e=10 p=10 n=0\"")
+		)
+		.arg(Arg::new("op")
+			.long("op")
+			.action(ArgAction::Append)
+			.help("Name einer Operation aus decay_series.yaml, kann mehrfach angegeben werden")
+		)
+		.arg(Arg::new("json")
+			.long("json")
+			.help("Gibt das Ergebnis als JSON aus, wie --format json")
+			.action(ArgAction::SetTrue)
+		)
+}
+
+fn find_operation<'a>(operations: &'a [Operation], name: &str) -> Option<&'a Operation> {
+	operations.iter()
+		.find(|operation| operation.display == name)
+		.or_else(|| operations.iter().find(|operation| operation.display.eq_ignore_ascii_case(name)))
+}
+
+/// Start and operations of the matches, fails if the start is invalid or an operation is unknown
+pub fn arguments<'a>(matches: &ArgMatches, operations: &'a [Operation]) -> Result<(State, Vec<&'a Operation>), String> {
+	let start = match matches.get_one::<String>("start").map(|start| start.parse::<State>()) {
+		Some(Ok(start)) => start,
+		Some(Err(err)) => return Err(format!("Ungültiger Startzustand: {}", err)),
+		None => State::default(),
+	};
+
+	let names: Vec<&String> = matches.get_many::<String>("op").map(|ops| ops.collect()).unwrap_or_default();
+	let mut selected: Vec<&Operation> = Vec::with_capacity(names.len());
+	for name in names {
+		match find_operation(operations, name) {
+			Some(operation) => selected.push(operation),
+			None => return Err(format!("Unbekannte Operation: {}", name)),
+		}
+	}
+	Ok((start, selected))
+}
+
+/**
+Applies the operations of the matches to their start, stops at the first operation which is not possible.
+Fails if the arguments are invalid
+ */
+pub fn decay(matches: &ArgMatches, operations: &[Operation], classifications: &[Classification], rng: &mut impl Rng) -> Result<DecayReport, String> {
+	let (start, selected) = arguments(matches, operations)?;
+
+	let mut report = DecayReport {
+		start,
+		steps: Vec::with_capacity(selected.len()),
+		state: start,
+		description: String::new(),
+		error: None,
+	};
+	for operation in selected {
+		if let Err(reason) = operation.check(&report.state, classifications) {
+			report.error = Some(format!("{}: {}", operation.display, reason));
+			break;
+		}
+		let success = operation.succeeds(rng);
+		if success {
+			report.state = operation.apply(report.state);
+		}
+		report.steps.push(Step {
+			operation: operation.display.clone(),
+			state: report.state,
+			success,
+		});
+	}
+	report.description = report.state.get_description(classifications);
+	Ok(report)
+}
+
+/// Records the result of [`decay`] with its arguments, so `würfeln verify` can apply them again
+pub fn record(matches: &ArgMatches, report: &DecayReport, history: &History) {
+	let mut words = vec!["--start".to_string(), repl::quote(matches.get_one::<String>("start").map(String::as_str).unwrap_or_default())];
+	for operation in matches.get_many::<String>("op").into_iter().flatten() {
+		words.push("--op".to_string());
+		words.push(repl::quote(operation));
+	}
+	history.record(Kind::Decay, words.join(" "), report, format!("Ergebnis: {}\n{}", report.description, report.state));
+}
+
+/**
+Runs the subcommand and returns the exit code
+ */
+pub fn run(matches: &ArgMatches, operations: &[Operation], classifications: &[Classification], format: Format, history: &History, rng: &mut impl Rng) -> i32 {
+	let report = match decay(matches, operations, classifications, rng) {
+		Ok(report) => report,
+		Err(err) => {
+			edbgprintln!("{}", err);
+			return EXIT_USAGE;
+		},
+	};
+
+	let format = if matches.get_flag("json") { Format::Json } else { format };
+	output::print(format, &report, || {
+		dbgprintln!("Start: {}\n{}\n", report.start.get_description(classifications), report.start);
+		for step in &report.steps {
+			if step.success {
+				dbgprintln!("{}: {}\n{}\n", step.operation, step.state.get_description(classifications), step.state);
+			} else {
+				dbgprintln!("{}: fehlgeschlagen\n", step.operation);
+			}
+		}
+		dbgprintln!("Ergebnis: {}", report.description);
+	});
+	record(matches, &report, history);
+
+	match report.error {
+		Some(error) => {
+			edbgprintln!("Nicht möglich! {}", error);
+			EXIT_FAILURE
+		},
+		None => EXIT_SUCCESS,
+	}
+}
@@ -0,0 +1,74 @@
+use crate::commands::{EXIT_FAILURE, EXIT_SUCCESS, EXIT_USAGE};
+use crate::disadvantage::{self, Character, Disadvantage, DisadvantageReport};
+use crate::history::{History, Kind};
+use crate::output::{self, Format};
+use crate::{dbgprintln, edbgprintln};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use rand::Rng;
+
+pub fn command() -> Command {
+	Command::new("disadvantage")
+		.about("Würfelt zufällige, miteinander kombinierbare Nachteile")
+		.arg(Arg::new("amount")
+			.short('n')
+			.long("amount")
+			.default_value("1")
+			.value_parser(clap::value_parser!(usize))
+			.help("Anzahl verschiedener Nachteile")
+		)
+		.arg(Arg::new("attributes")
+			.long("attributes")
+			.help("Attribute des Charakters, z.B. \"IN=3 UM=2\"")
+		)
+		.arg(Arg::new("taken")
+			.long("taken")
+			.action(ArgAction::Append)
+			.help("Nachteil, den der Charakter schon hat, kann mehrfach angegeben werden")
+		)
+}
+
+/// The character of the matches, fails if the attributes are invalid
+pub fn character(matches: &ArgMatches) -> Result<Character, String> {
+	let attributes = match matches.get_one::<String>("attributes") {
+		Some(input) => disadvantage::parse_attributes(input)?,
+		None => Default::default(),
+	};
+	Ok(Character {
+		attributes,
+		disadvantages: matches.get_many::<String>("taken").into_iter().flatten().cloned().collect(),
+	})
+}
+
+/**
+Runs the subcommand and returns the exit code
+ */
+pub fn run(matches: &ArgMatches, disadvantages: &[Disadvantage], format: Format, history: &History, rng: &mut impl Rng) -> i32 {
+	let character = match character(matches) {
+		Ok(character) => character,
+		Err(err) => {
+			edbgprintln!("{}", err);
+			return EXIT_USAGE;
+		},
+	};
+
+	let amount = matches.get_one::<usize>("amount").copied().unwrap_or(1);
+	let drawn = disadvantage::draw(disadvantages, &character, amount, rng);
+	let report = DisadvantageReport::new(&drawn);
+	output::print(format, &report, || {
+		for rando in &drawn {
+			dbgprintln!("{}", rando);
+			if let Some(ip) = rando.ip() {
+				dbgprintln!("Erhaltene IP: {}", ip);
+			}
+		}
+		if drawn.len() > 1 {
+			dbgprintln!("IP insgesamt: {}", report.total_ip);
+		}
+	});
+	history.record(Kind::Disadvantage, amount.to_string(), &report, &report);
+	if drawn.len() < amount {
+		edbgprintln!("Es gibt nur {} kombinierbare Nachteile", drawn.len());
+		return EXIT_FAILURE;
+	}
+	EXIT_SUCCESS
+}
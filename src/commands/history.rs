@@ -0,0 +1,83 @@
+use crate::commands::EXIT_SUCCESS;
+use crate::history::{self, Entry, History, HistoryFilter, Kind};
+use crate::output::{self, Format};
+use crate::{dbgprintln, edbgprintln};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+
+/**
+Filters shared by every subcommand which reads the history
+ */
+pub fn filter_args(command: Command) -> Command {
+	command
+		.arg(Arg::new("type")
+			.long("type")
+			.value_parser(clap::value_parser!(Kind))
+			.help("Nur Würfe dieser Art")
+		)
+		.arg(Arg::new("player")
+			.long("player")
+			.help("Nur Würfe dieses Spielers")
+		)
+		.arg(Arg::new("since")
+			.long("since")
+			.value_parser(history::parse_date)
+			.help("Nur Würfe ab diesem Tag, z.B. 19.10.2026")
+		)
+		.arg(Arg::new("until")
+			.long("until")
+			.value_parser(history::parse_date)
+			.help("Nur Würfe bis einschließlich diesem Tag")
+		)
+		.arg(Arg::new("session")
+			.long("session")
+			.action(ArgAction::SetTrue)
+			.help("Nur Würfe der letzten Sitzung")
+		)
+}
+
+/**
+Builds the filter of [`filter_args`], `--session` picks the newest session in `entries`
+ */
+pub fn filter(matches: &ArgMatches, entries: &[Entry]) -> HistoryFilter {
+	HistoryFilter {
+		kind: matches.get_one::<Kind>("type").copied(),
+		player: matches.get_one::<String>("player").cloned(),
+		since: matches.get_one::<u64>("since").copied(),
+		until: matches.get_one::<u64>("until").copied(),
+		// Without any session in the history nothing matches, 0 is never the start of one
+		session: matches.get_flag("session").then(|| entries.iter().filter_map(|entry| entry.session).max().unwrap_or_default()),
+	}
+}
+
+pub fn command() -> Command {
+	filter_args(Command::new("history"))
+		.about("Zeigt frühere Würfe aus dem Verlauf")
+		.arg(Arg::new("last")
+			.short('n')
+			.long("last")
+			.value_parser(clap::value_parser!(usize))
+			.help("Nur die letzten n passenden Würfe")
+		)
+}
+
+/**
+Runs the subcommand and returns the exit code
+ */
+pub fn run(matches: &ArgMatches, history: &History, format: Format) -> i32 {
+	let entries = history.load();
+	let mut found = filter(matches, &entries).apply(&entries);
+	if let Some(last) = matches.get_one::<usize>("last") {
+		found.drain(..found.len().saturating_sub(*last));
+	}
+
+	let report: Vec<&Entry> = found.iter().map(|(_, entry)| *entry).collect();
+	output::print(format, &report, || {
+		for (number, entry) in &found {
+			dbgprintln!("{}. {}\n", number, entry);
+		}
+	});
+	if found.is_empty() {
+		edbgprintln!("Keine passenden Würfe im Verlauf");
+	}
+	EXIT_SUCCESS
+}
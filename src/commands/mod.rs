@@ -0,0 +1,39 @@
+pub mod colored;
+pub mod crit;
+pub mod decay;
+pub mod disadvantage;
+pub mod history;
+pub mod roll;
+pub mod spell;
+pub mod stats;
+pub mod table;
+pub mod verify;
+
+use crate::history::{History, Kind};
+use serde::Serialize;
+
+/// Everything worked
+pub const EXIT_SUCCESS: i32 = 0;
+/// The roll or operation itself was not possible
+pub const EXIT_FAILURE: i32 = 1;
+/// The arguments could not be parsed, same code as clap uses
+pub const EXIT_USAGE: i32 = 2;
+
+/**
+A roll of a subcommand before it is printed, the command line, the REPL and the TUI record it the same way
+ */
+pub struct Rolled<R> {
+	pub kind: Kind,
+	/// What was rolled, as shown in the history
+	pub input: String,
+	/// Machine readable result, printed with --format and stored in the history
+	pub report: R,
+	/// Readable result for the history
+	pub text: String,
+}
+
+impl<R: Serialize> Rolled<R> {
+	pub fn record(&self, history: &History) {
+		history.record(self.kind, self.input.as_str(), &self.report, &self.text);
+	}
+}
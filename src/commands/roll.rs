@@ -0,0 +1,72 @@
+use crate::commands::{Rolled, EXIT_SUCCESS, EXIT_USAGE};
+use crate::dice::normal_dice::{self, Dices, RollReport, Results};
+use crate::history::{History, Kind};
+use crate::output::{self, Format};
+use crate::edbgprintln;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use rand::Rng;
+
+pub fn command() -> Command {
+	Command::new("roll")
+		.about("Würfelt normale Würfel, z.B. 5d6")
+		.arg(Arg::new("dice")
+			.required(true)
+			.action(ArgAction::Append)
+			.help("Anzahl und Seiten, z.B. 5d6 oder W20, kann mehrfach angegeben werden")
+		)
+}
+
+/// Amount and sides of the dice in the matches, fails if a dice is invalid or not allowed
+pub fn dice(matches: &ArgMatches, allowed: &Dices) -> Result<Vec<(usize, u8)>, String> {
+	let mut rolls = vec![];
+	for expression in matches.get_many::<String>("dice").into_iter().flatten() {
+		let (amount, sides) = normal_dice::parse_expression(expression)?;
+		if !allowed.dices.contains(&sides) {
+			return Err(format!("Ein W{} ist nicht erlaubt, erlaubt sind: {:?}", sides, allowed.dices));
+		}
+		rolls.push((amount, sides));
+	}
+	Ok(rolls)
+}
+
+/**
+Rolls the dice of the matches, fails if a dice is invalid or not allowed.
+The results are returned as well, the text output is printed from them
+ */
+pub fn evaluate(matches: &ArgMatches, allowed: &Dices, old_style: bool, rng: &mut impl Rng) -> Result<(Vec<Results>, Rolled<Vec<RollReport>>), String> {
+	let rolls = dice(matches, allowed)?;
+	let expressions: Vec<&String> = matches.get_many::<String>("dice").into_iter().flatten().collect();
+	let results: Vec<Results> = rolls.into_iter()
+		.map(|(amount, sides)| normal_dice::roll(amount, sides, old_style, rng))
+		.collect();
+	let reports: Vec<RollReport> = results.iter().map(Results::report).collect();
+	let text: Vec<String> = reports.iter().map(RollReport::to_string).collect();
+	let input: Vec<&str> = expressions.iter().map(|expression| expression.as_str()).collect();
+	let rolled = Rolled {
+		kind: Kind::Roll,
+		input: input.join(" "),
+		report: reports,
+		text: text.join("\n"),
+	};
+	Ok((results, rolled))
+}
+
+/**
+Runs the subcommand and returns the exit code
+ */
+pub fn run(matches: &ArgMatches, allowed: &Dices, old_style: bool, no_summary: bool, format: Format, history: &History, rng: &mut impl Rng) -> i32 {
+	let (results, rolled) = match evaluate(matches, allowed, old_style, rng) {
+		Ok(evaluated) => evaluated,
+		Err(err) => {
+			edbgprintln!("{}", err);
+			return EXIT_USAGE;
+		},
+	};
+	output::print(format, &rolled.report, || {
+		for result in &results {
+			result.print_results(old_style, no_summary);
+		}
+	});
+	rolled.record(history);
+	EXIT_SUCCESS
+}
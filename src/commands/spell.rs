@@ -0,0 +1,88 @@
+use crate::commands::{Rolled, EXIT_FAILURE, EXIT_SUCCESS, EXIT_USAGE};
+use crate::history::{History, Kind};
+use crate::output::{self, Format};
+use crate::spell::{self, DrawnSpell, Spells};
+use crate::{dbgprintln, edbgprintln};
+use clap::{Arg, ArgMatches, Command};
+use rand::Rng;
+
+pub fn command() -> Command {
+	Command::new("spell")
+		.about("Würfelt zufällige Zauber")
+		.arg(Arg::new("category")
+			.help("Kategorie, z.B. Kampfzauber. Ohne Kategorie wird aus allen gewürfelt")
+		)
+		.arg(Arg::new("amount")
+			.short('n')
+			.long("amount")
+			.default_value("1")
+			.value_parser(clap::value_parser!(usize))
+			.help("Anzahl verschiedener Zauber")
+		)
+}
+
+/// The categories to draw from, all without a category in the matches
+pub fn categories<'a>(matches: &ArgMatches, spells: &'a [Spells]) -> Result<Vec<&'a Spells>, String> {
+	match matches.get_one::<String>("category") {
+		Some(name) => match spell::find(spells, name) {
+			Some(category) => Ok(vec![category]),
+			None => {
+				let known: Vec<&str> = spells.iter().map(|category| category.name.as_str()).collect();
+				Err(format!("Unbekannte Kategorie: {}, bekannt sind: {}", name, known.join(", ")))
+			},
+		},
+		None => Ok(spells.iter().collect()),
+	}
+}
+
+/**
+Draws the spells of the matches, fails if the category is unknown.
+Fewer spells than requested are drawn if there are not enough, see [`shortage`]
+ */
+pub fn evaluate(matches: &ArgMatches, spells: &[Spells], rng: &mut impl Rng) -> Result<Rolled<Vec<DrawnSpell>>, String> {
+	let categories = categories(matches, spells)?;
+	let amount = matches.get_one::<usize>("amount").copied().unwrap_or(1);
+	let drawn = spell::draw(&categories, amount, rng);
+	let report: Vec<DrawnSpell> = drawn.iter()
+		.map(|(category, spell)| DrawnSpell::new(category, spell))
+		.collect();
+	let text: Vec<String> = drawn.iter().map(|(category, spell)| format!("{}: {}", category, spell)).collect();
+	let category = matches.get_one::<String>("category").map(String::as_str).unwrap_or("Alle");
+	Ok(Rolled {
+		kind: Kind::Spell,
+		input: format!("{} x{}", category, amount),
+		report,
+		text: text.join("\n"),
+	})
+}
+
+/// Why fewer spells than requested were drawn
+pub fn shortage(matches: &ArgMatches, rolled: &Rolled<Vec<DrawnSpell>>) -> Option<String> {
+	let amount = matches.get_one::<usize>("amount").copied().unwrap_or(1);
+	(rolled.report.len() < amount)
+		.then(|| format!("Es gibt nur {} verschiedene Zauber mit einer Gewichtung über 0", rolled.report.len()))
+}
+
+/**
+Runs the subcommand and returns the exit code
+ */
+pub fn run(matches: &ArgMatches, spells: &[Spells], format: Format, history: &History, rng: &mut impl Rng) -> i32 {
+	let rolled = match evaluate(matches, spells, rng) {
+		Ok(rolled) => rolled,
+		Err(err) => {
+			edbgprintln!("{}", err);
+			return EXIT_USAGE;
+		},
+	};
+	output::print(format, &rolled.report, || {
+		for line in rolled.text.lines() {
+			dbgprintln!("{}", line);
+		}
+	});
+	rolled.record(history);
+	if let Some(shortage) = shortage(matches, &rolled) {
+		edbgprintln!("{}", shortage);
+		return EXIT_FAILURE;
+	}
+	EXIT_SUCCESS
+}
@@ -0,0 +1,27 @@
+use crate::commands::{history, EXIT_SUCCESS};
+use crate::dbgprintln;
+use crate::dice::colored_dice::ColoredDices;
+use crate::history::{Entry, History};
+use crate::output::{self, Format};
+use crate::statistics::Report;
+use clap::{ArgMatches, Command};
+
+pub fn command() -> Command {
+	history::filter_args(Command::new("stats"))
+		.about("Statistik und Glück der Spieler aus dem Verlauf")
+}
+
+/**
+Runs the subcommand and returns the exit code
+ */
+pub fn run(matches: &ArgMatches, history: &History, colored_dice: &ColoredDices, format: Format) -> i32 {
+	let entries = history.load();
+	let found: Vec<&Entry> = history::filter(matches, &entries)
+		.apply(&entries)
+		.into_iter()
+		.map(|(_, entry)| entry)
+		.collect();
+	let report = Report::new(&found, colored_dice);
+	output::print(format, &report, || dbgprintln!("{}", report));
+	EXIT_SUCCESS
+}
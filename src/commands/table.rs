@@ -0,0 +1,65 @@
+use crate::commands::{Rolled, EXIT_FAILURE, EXIT_SUCCESS, EXIT_USAGE};
+use crate::history::{History, Kind};
+use crate::output::{self, Format};
+use crate::table::{self, Table, TableResult};
+use crate::{dbgprintln, edbgprintln};
+use clap::{Arg, ArgMatches, Command};
+use rand::Rng;
+
+pub fn command() -> Command {
+	Command::new("table")
+		.about("Würfelt auf einer eigenen Tabelle aus dem tables Ordner")
+		.arg(Arg::new("name")
+			.required(true)
+			.help("Name der Tabelle")
+		)
+}
+
+/// The table named in the matches
+pub fn find<'a>(matches: &ArgMatches, tables: &'a [Table]) -> Result<&'a Table, String> {
+	let name = matches.get_one::<String>("name").map(String::as_str).unwrap_or_default();
+	table::find(tables, name).ok_or_else(|| {
+		let known: Vec<&str> = tables.iter().map(|table| table.name.as_str()).collect();
+		format!("Unbekannte Tabelle: {}, bekannt sind: {}", name, known.join(", "))
+	})
+}
+
+/// Rolls on the table, fails if a referenced table is missing
+pub fn evaluate(table: &Table, tables: &[Table], rng: &mut impl Rng) -> Result<Rolled<Vec<TableResult>>, String> {
+	let results = table.roll(tables, rng).map_err(|err| err.to_string())?;
+	let text: Vec<String> = results.iter().map(TableResult::to_string).collect();
+	Ok(Rolled {
+		kind: Kind::Table,
+		input: table.name.clone(),
+		report: results,
+		text: text.join("\n"),
+	})
+}
+
+/**
+Runs the subcommand and returns the exit code
+ */
+pub fn run(matches: &ArgMatches, tables: &[Table], format: Format, history: &History, rng: &mut impl Rng) -> i32 {
+	let table = match find(matches, tables) {
+		Ok(table) => table,
+		Err(err) => {
+			edbgprintln!("{}", err);
+			return EXIT_USAGE;
+		},
+	};
+	match evaluate(table, tables, rng) {
+		Ok(rolled) => {
+			output::print(format, &rolled.report, || {
+				for line in rolled.text.lines() {
+					dbgprintln!("{}", line);
+				}
+			});
+			rolled.record(history);
+			EXIT_SUCCESS
+		},
+		Err(err) => {
+			edbgprintln!("{}", err);
+			EXIT_FAILURE
+		},
+	}
+}
@@ -0,0 +1,77 @@
+use crate::audit::{self, Rules};
+use crate::commands::{EXIT_FAILURE, EXIT_SUCCESS, EXIT_USAGE};
+use crate::history::{Entry, History};
+use crate::output::{self, Format};
+use crate::rng::Seed;
+use crate::{dbgprintln, edbgprintln};
+use clap::{Arg, ArgMatches, Command};
+use std::fs;
+use std::path::PathBuf;
+
+pub fn command() -> Command {
+	Command::new("verify")
+		.about("Prüft mit dem aufgedeckten Seed einer Audit Sitzung, dass kein Wurf verändert wurde")
+		// Not "seed", that is the global --seed
+		.arg(Arg::new("revealed")
+			.value_name("SEED")
+			.required(true)
+			.value_parser(clap::value_parser!(Seed))
+			.help("Der am Ende der Sitzung aufgedeckte Seed")
+		)
+		.arg(Arg::new("log")
+			.value_parser(clap::value_parser!(PathBuf))
+			.help("Exportierter Verlauf (history --format json oder yaml) oder history.jsonl, ohne Angabe der eigene Verlauf")
+		)
+		.arg(Arg::new("commitment")
+			.long("commitment")
+			.help("Das zu Beginn der Sitzung ausgegebene Commitment")
+		)
+}
+
+/**
+Reads an export of `würfeln history` or the history file itself
+ */
+fn parse_log(content: &str) -> Result<Vec<Entry>, String> {
+	if let Ok(entries) = serde_json::from_str::<Vec<Entry>>(content) {
+		return Ok(entries);
+	}
+	let lines: Result<Vec<Entry>, _> = content
+		.lines()
+		.filter(|line| !line.trim().is_empty())
+		.map(serde_json::from_str::<Entry>)
+		.collect();
+	if let Ok(entries) = lines {
+		return Ok(entries);
+	}
+	serde_yaml::from_str::<Vec<Entry>>(content).map_err(|err| err.to_string())
+}
+
+/**
+Runs the subcommand and returns the exit code
+ */
+pub fn run(matches: &ArgMatches, rules: &Rules, history: &History, format: Format) -> i32 {
+	let entries = match matches.get_one::<PathBuf>("log") {
+		Some(path) => match fs::read_to_string(path).map_err(|err| err.to_string()).and_then(|content| parse_log(&content)) {
+			Ok(entries) => entries,
+			Err(err) => {
+				edbgprintln!("{}: {}", path.display(), err);
+				return EXIT_USAGE;
+			},
+		},
+		None => history.load(),
+	};
+	let Some(seed) = matches.get_one::<Seed>("revealed").copied() else {
+		return EXIT_USAGE;
+	};
+	match audit::verify(&entries, seed, matches.get_one::<String>("commitment").map(String::as_str), rules) {
+		Ok(verification) => {
+			output::print(format, &verification, || dbgprintln!("{}", verification));
+			if verification.passed() { EXIT_SUCCESS } else { EXIT_FAILURE }
+		},
+		// The seed is not the one committed to
+		Err(err) => {
+			edbgprintln!("{}", err);
+			EXIT_FAILURE
+		},
+	}
+}
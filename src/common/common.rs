@@ -1,9 +1,13 @@
+use ansi_term::Colour;
 use directories::ProjectDirs;
-use std::fs::create_dir_all;
-use std::path::{PathBuf, MAIN_SEPARATOR};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::{create_dir_all, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
 use std::process::exit;
 use rand::Rng;
-use crate::dbgprintln;
+use crate::edbgprintln;
 
 pub fn settings_path(file: &str) -> PathBuf {
 	let dir = match ProjectDirs::from("", "", "würfeln") {
@@ -27,11 +31,41 @@ pub fn settings_path(file: &str) -> PathBuf {
 		}
 	};
 
-    dbgprintln!("Loading from file: {}", dir.to_str().unwrap());
+	#[cfg(debug_assertions)]
+	edbgprintln!("Loading from file: {}", dir.to_str().unwrap());
 
 	dir
 }
 
+/**
+Reads `file` or `default_file` in the data directory. A missing file is created with the defaults,
+a broken file is reported and left untouched for the user to fix, the defaults are used instead
+ */
+pub fn load_yaml<T: Serialize + DeserializeOwned>(file: Option<&str>, default_file: &str, defaults: fn() -> T, created: &str) -> T {
+	let alt = settings_path(default_file);
+	let file_name = file.unwrap_or(alt.to_str().unwrap());
+	if Path::new(file_name).exists() {
+		let parsed = File::open(file_name)
+			.map_err(|err| err.to_string())
+			.and_then(|file| serde_yaml::from_reader::<BufReader<File>, T>(BufReader::new(file)).map_err(|err| err.to_string()));
+		match parsed {
+			Ok(value) => value,
+			Err(err) => {
+				edbgprintln!("{}", Colour::RGB(255, 0, 0).paint(format!("{}: {}", file_name, err)));
+				defaults()
+			}
+		}
+	} else {
+		let value = defaults();
+		match File::create(file_name).map_err(|err| err.to_string())
+			.and_then(|file| serde_yaml::to_writer(BufWriter::new(file), &value).map_err(|err| err.to_string())) {
+			Ok(_) => edbgprintln!("{}", created),
+			Err(err) => edbgprintln!("{}", Colour::RGB(255, 0, 0).paint(format!("{}: {}", file_name, err))),
+		}
+		value
+	}
+}
+
 pub trait Loadable<T> {
 	fn load(file: Option<&str>) -> T;
 }
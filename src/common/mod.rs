@@ -1,4 +1,5 @@
+#[allow(clippy::module_inception)]
 mod common;
 mod macros;
 
-pub use common::{settings_path, Loadable, Rollable};
\ No newline at end of file
+pub use common::{load_yaml, settings_path, Loadable, Rollable};
\ No newline at end of file
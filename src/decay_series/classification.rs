@@ -0,0 +1,264 @@
+use crate::common::{load_yaml, Loadable};
+use crate::decay_series::state::State;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+const CLASSIFICATION_FILE: &str = "classification.yaml";
+
+/**
+One of the three particle counts of a [`State`]
+ */
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Particle {
+    Electrons,
+    Protons,
+    Neutrons,
+}
+
+impl Particle {
+    pub fn count(&self, state: &State) -> i64 {
+        match self {
+            Particle::Electrons => state.electrons,
+            Particle::Protons => state.protons,
+            Particle::Neutrons => state.neutrons,
+        }
+    }
+}
+
+impl Display for Particle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Particle::Electrons => write!(f, "Elektronen"),
+            Particle::Protons => write!(f, "Protonen"),
+            Particle::Neutrons => write!(f, "Neutronen"),
+        }
+    }
+}
+
+/**
+Either a particle count of the state or a fixed number
+ */
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Operand {
+    Particle(Particle),
+    Value(i64),
+}
+
+impl Operand {
+    fn value(&self, state: &State) -> i64 {
+        match self {
+            Operand::Particle(particle) => particle.count(state),
+            Operand::Value(value) => *value,
+        }
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operand::Particle(particle) => write!(f, "{}", particle),
+            Operand::Value(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash, Serialize, Deserialize)]
+pub enum Comparison {
+    #[serde(rename = "==")]
+    Equal,
+    #[serde(rename = "!=")]
+    NotEqual,
+    #[serde(rename = "<")]
+    Less,
+    #[serde(rename = "<=")]
+    LessEqual,
+    #[serde(rename = ">")]
+    Greater,
+    #[serde(rename = ">=")]
+    GreaterEqual,
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+            Comparison::Less => "<",
+            Comparison::LessEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterEqual => ">=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/**
+A single condition like `neutrons != 0` or `protons < electrons`
+ */
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash, Serialize, Deserialize)]
+pub struct Condition {
+    pub left: Operand,
+    pub op: Comparison,
+    pub right: Operand,
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.left, self.op, self.right)
+    }
+}
+
+impl Condition {
+    pub fn matches(&self, state: &State) -> bool {
+        let left = self.left.value(state);
+        let right = self.right.value(state);
+        match self.op {
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::Less => left < right,
+            Comparison::LessEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterEqual => left >= right,
+        }
+    }
+}
+
+/**
+A category of the decay series. The first category whose conditions all hold describes the state.
+The template may contain `{electrons}`, `{protons}` and `{neutrons}`.
+`number` selects the count which is used as the number inside the category, e.g. for the bestiary.
+ */
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash, Serialize, Deserialize)]
+pub struct Classification {
+    pub category: String,
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    pub template: String,
+    #[serde(default = "Classification::default_number")]
+    pub number: Particle,
+}
+
+impl Loadable<Vec<Self>> for Classification {
+    fn load(file: Option<&str>) -> Vec<Self> {
+        load_yaml(file, CLASSIFICATION_FILE, Classification::defaults, "Neue Klassifikationen wurden erzeugt")
+    }
+}
+
+impl Classification {
+    pub fn defaults() -> Vec<Self> {
+        use Comparison::*;
+        use Operand::Value;
+        use Particle::*;
+
+        let condition = |left: Operand, op: Comparison, right: Operand| Condition { left, op, right };
+        let electrons = Operand::Particle(Electrons);
+        let protons = Operand::Particle(Protons);
+        let neutrons = Operand::Particle(Neutrons);
+
+        vec![
+            Classification {
+                category: "Monster".to_string(),
+                conditions: vec![
+                    condition(neutrons, Equal, Value(0)),
+                    condition(electrons, Equal, protons),
+                ],
+                template: "Monster Nr. {protons}".to_string(),
+                number: Protons,
+            },
+            Classification {
+                category: "Pflanze der 2. Generation".to_string(),
+                conditions: vec![
+                    condition(neutrons, NotEqual, Value(0)),
+                    condition(protons, Equal, electrons),
+                ],
+                template: "Pflanze der 2. Generation Nr. {protons}".to_string(),
+                number: Protons,
+            },
+            Classification {
+                category: "Pflanze der 3. Generation".to_string(),
+                conditions: vec![
+                    condition(neutrons, NotEqual, Value(0)),
+                    condition(protons, Less, electrons),
+                ],
+                template: "Pflanze der 3. Generation Nr. {protons}".to_string(),
+                number: Protons,
+            },
+            Classification {
+                category: "Pflanze der 1. Generation".to_string(),
+                conditions: vec![
+                    condition(neutrons, NotEqual, Value(0)),
+                    condition(electrons, Less, protons),
+                ],
+                template: "Pflanze der 1. Generation Nr. {protons}".to_string(),
+                number: Protons,
+            },
+        ]
+    }
+
+    fn default_number() -> Particle {
+        Particle::Protons
+    }
+
+    pub fn matches(&self, state: &State) -> bool {
+        self.conditions.iter().all(|condition| condition.matches(state))
+    }
+
+    pub fn render(&self, state: &State) -> String {
+        self.template
+            .replace("{electrons}", &state.electrons.to_string())
+            .replace("{protons}", &state.protons.to_string())
+            .replace("{neutrons}", &state.neutrons.to_string())
+    }
+
+    /**
+    Returns the first classification matching the state
+     */
+    pub fn find<'a>(rules: &'a [Self], state: &State) -> Option<&'a Self> {
+        rules.iter().find(|rule| rule.matches(state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decay_series::classification::{Classification, Comparison, Condition, Operand, Particle};
+    use crate::decay_series::state::State;
+
+    #[test]
+    fn test_defaults_match_original_rules() {
+        let rules = Classification::defaults();
+        let describe = |electrons, protons, neutrons| {
+            State { electrons, protons, neutrons }.get_description(&rules)
+        };
+        assert_eq!(describe(7, 7, 0), "Monster Nr. 7");
+        assert_eq!(describe(3, 3, 2), "Pflanze der 2. Generation Nr. 3");
+        assert_eq!(describe(4, 3, 2), "Pflanze der 3. Generation Nr. 3");
+        assert_eq!(describe(2, 3, 2), "Pflanze der 1. Generation Nr. 3");
+        assert_eq!(describe(2, 3, 0), "Nicht abgedeckt");
+    }
+
+    #[test]
+    fn test_custom_rule_from_yaml() {
+        let yaml = "
+- category: Mineral
+  conditions:
+    - { left: electrons, op: '==', right: 0 }
+    - { left: neutrons, op: '>=', right: 5 }
+  template: 'Mineral Nr. {neutrons}'
+";
+        let rules: Vec<Classification> = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            rules[0].conditions[1],
+            Condition {
+                left: Operand::Particle(Particle::Neutrons),
+                op: Comparison::GreaterEqual,
+                right: Operand::Value(5),
+            }
+        );
+        let state = State { electrons: 0, protons: 1, neutrons: 6 };
+        assert_eq!(state.get_description(&rules), "Mineral Nr. 6");
+        let state = State { electrons: 0, protons: 1, neutrons: 4 };
+        assert_eq!(state.get_description(&rules), "Nicht abgedeckt");
+    }
+}
@@ -0,0 +1,182 @@
+use crate::decay_series::classification::Classification;
+use crate::decay_series::operation::Operation;
+use crate::decay_series::state::State;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Deepest exploration, every further operation multiplies the reachable states
+pub const MAX_DEPTH: usize = 30;
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Node {
+    pub id: usize,
+    pub state: State,
+    pub label: String,
+    /// Minimal number of operations needed to reach this state
+    pub depth: usize,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub operation: String,
+}
+
+/**
+All states reachable from a start state, the start state always has the id 0
+ */
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+impl Graph {
+    /**
+    Explores every state reachable within `max_depth`, at most [`MAX_DEPTH`], operations with a breadth first search.
+    Operations which are not available in a state (see [`Operation::check`]) are skipped
+     */
+    pub fn explore(start: State, operations: &[Operation], classifications: &[Classification], max_depth: usize) -> Self {
+        let max_depth = max_depth.min(MAX_DEPTH);
+        let mut graph = Graph::default();
+        let mut ids: HashMap<State, usize> = HashMap::new();
+        let mut queue: VecDeque<usize> = VecDeque::new();
+
+        graph.add_node(start, 0, classifications, &mut ids);
+        queue.push_back(0);
+
+        while let Some(id) = queue.pop_front() {
+            let Node { state, depth, .. } = graph.nodes[id];
+            if depth >= max_depth {
+                continue;
+            }
+            for operation in operations {
+                if operation.check(&state, classifications).is_err() {
+                    continue;
+                }
+                let next = operation.apply(state);
+                let to = match ids.get(&next) {
+                    Some(to) => *to,
+                    None => {
+                        let to = graph.add_node(next, depth + 1, classifications, &mut ids);
+                        queue.push_back(to);
+                        to
+                    }
+                };
+                graph.edges.push(Edge {
+                    from: id,
+                    to,
+                    operation: operation.display.clone(),
+                });
+            }
+        }
+        graph
+    }
+
+    fn add_node(&mut self, state: State, depth: usize, classifications: &[Classification], ids: &mut HashMap<State, usize>) -> usize {
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            id,
+            state,
+            label: state.get_description(classifications),
+            depth,
+        });
+        ids.insert(state, id);
+        id
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut buffer = String::from("digraph zerfallsreihe {\n");
+        for node in &self.nodes {
+            buffer += &format!(
+                "    n{} [label=\"{}\\nE: {} P: {} N: {}\"];\n",
+                node.id,
+                escape(&node.label),
+                node.state.electrons,
+                node.state.protons,
+                node.state.neutrons
+            );
+        }
+        for edge in &self.edges {
+            buffer += &format!("    n{} -> n{} [label=\"{}\"];\n", edge.from, edge.to, escape(&edge.operation));
+        }
+        buffer += "}\n";
+        buffer
+    }
+
+    /**
+    The files [`Graph::export`] writes, the extension is appended so dots in the name are kept
+     */
+    pub fn files(base: &Path) -> (PathBuf, PathBuf) {
+        let with = |extension: &str| {
+            let mut file = base.as_os_str().to_owned();
+            file.push(extension);
+            PathBuf::from(file)
+        };
+        (with(".dot"), with(".json"))
+    }
+
+    /**
+    Writes the graph as `<base>.dot` and `<base>.json`
+     */
+    pub fn export(&self, base: &Path) -> std::io::Result<()> {
+        let (dot, json) = Graph::files(base);
+        let mut dot = BufWriter::new(File::create(dot)?);
+        dot.write_all(self.to_dot().as_bytes())?;
+        dot.flush()?;
+
+        let json = BufWriter::new(File::create(json)?);
+        serde_json::to_writer_pretty(json, self)?;
+        Ok(())
+    }
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decay_series::classification::Classification;
+    use crate::decay_series::graph::Graph;
+    use crate::decay_series::operation::Operation;
+    use crate::decay_series::state::State;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_files_keep_dots() {
+        let (dot, json) = Graph::files(Path::new("daten/v1.2"));
+        assert_eq!(dot, PathBuf::from("daten/v1.2.dot"));
+        assert_eq!(json, PathBuf::from("daten/v1.2.json"));
+    }
+
+    #[test]
+    fn test_explore() {
+        let operations = vec![
+            Operation {
+                display: "Alpha".to_string(),
+                protons: Some(-2),
+                neutrons: Some(-2),
+                ..Default::default()
+            },
+            Operation {
+                display: "Gamma".to_string(),
+                electrons: Some(-1),
+                ..Default::default()
+            },
+        ];
+        let start = State { electrons: 1, protons: 4, neutrons: 4 };
+        let graph = Graph::explore(start, &operations, &Classification::defaults(), 2);
+
+        // (1,4,4) -> (1,2,2), (0,4,4) -> (1,0,0), (0,2,2); (0,2,2) is reached twice
+        assert_eq!(graph.nodes.len(), 5);
+        assert_eq!(graph.edges.len(), 5);
+        assert_eq!(graph.nodes[0].state, start);
+        assert!(graph.nodes.iter().all(|node| node.depth <= 2));
+        assert!(graph.to_dot().contains("n0 -> n1 [label=\"Alpha\"]"));
+    }
+}
@@ -1,5 +1,13 @@
+mod classification;
+mod graph;
 mod operation;
+mod session;
 mod state;
+mod walk;
 
+pub use classification::Classification;
+pub use graph::{Graph, MAX_DEPTH};
 pub use operation::Operation;
-pub use state::State;
\ No newline at end of file
+pub use session::Session;
+pub use state::State;
+pub use walk::{random_state, random_walk, Step, MAX_STEPS};
\ No newline at end of file
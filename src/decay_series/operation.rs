@@ -1,19 +1,106 @@
 use ansi_term::Colour;
 use crate::common::{settings_path, Loadable};
-use crate::dbgprintln;
-use serde::{Deserialize, Serialize};
+use crate::edbgprintln;
+use rand::distr::Uniform;
+use rand::Rng;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
 use std::fmt::{Display, Formatter};
 use std::fs::File;
 use std::io::{BufReader, BufWriter};
 use std::path::Path;
+use crate::decay_series::classification::{Classification, Condition};
 use crate::decay_series::state::State;
 
+/**
+What has to be paid to perform an operation. Colored dice are referenced by their long name
+ */
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash, Default, Serialize, Deserialize)]
+pub struct Cost {
+    #[serde(default)]
+    pub mana: u64,
+    #[serde(default)]
+    pub colored: BTreeMap<String, u64>,
+}
+
+impl Cost {
+    pub fn is_empty(&self) -> bool {
+        self.mana == 0 && self.colored.values().all(|amount| *amount == 0)
+    }
+
+    pub fn add(&mut self, other: &Cost) {
+        self.mana += other.mana;
+        for (dice, amount) in &other.colored {
+            *self.colored.entry(dice.clone()).or_insert(0) += amount;
+        }
+    }
+}
+
+impl Display for Cost {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut parts: Vec<String> = Vec::with_capacity(self.colored.len() + 1);
+        if self.mana != 0 {
+            parts.push(format!("{} Mana", self.mana));
+        }
+        parts.extend(self.colored
+            .iter()
+            .filter(|(_, amount)| **amount != 0)
+            .map(|(dice, amount)| format!("{} {}", amount, dice)));
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/**
+Why an operation can not be applied to a state
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub enum Unavailable {
+    Precondition(Condition),
+    Category(Vec<String>),
+    Negative,
+}
+
+impl Display for Unavailable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Unavailable::Precondition(condition) => write!(f, "Bedingung {} ist nicht erfüllt", condition),
+            Unavailable::Category(categories) => write!(f, "Nur möglich für: {}", categories.join(", ")),
+            Unavailable::Negative => write!(f, "Ergebnis wäre negativ"),
+        }
+    }
+}
+
+impl Error for Unavailable {}
+
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Serialize, Deserialize)]
 pub struct Operation {
     pub display: String,
     pub electrons: Option<i64>,
     pub protons: Option<i64>,
     pub neutrons: Option<i64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub preconditions: Vec<Condition>,
+    /// Categories of the current state in which the operation is possible, empty means all
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<Cost>,
+    /// Chance of success in percent
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "deserialize_probability")]
+    pub probability: Option<f32>,
+}
+
+/// Rejects chances outside of 0 to 100 percent
+fn deserialize_probability<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f32>, D::Error> {
+    let probability = Option::<f32>::deserialize(deserializer)?;
+    match probability {
+        Some(value) if !(0.0..=100.0).contains(&value) => {
+            Err(D::Error::custom(format!("probability {} liegt nicht zwischen 0 und 100", value)))
+        }
+        _ => Ok(probability),
+    }
 }
 
 impl Display for Operation {
@@ -30,8 +117,13 @@ impl Loadable<Vec<Self>> for Operation {
         if Path::new(file_name).exists() {
             let file = File::open(file_name).unwrap();
             let buf_reader = BufReader::new(file);
-            serde_yaml::from_reader::<BufReader<File>, Vec<Operation>>(buf_reader)
-                .unwrap_or(Operation::defaults())
+            match serde_yaml::from_reader::<BufReader<File>, Vec<Operation>>(buf_reader) {
+                Ok(operations) => operations,
+                Err(err) => {
+                    edbgprintln!("{}", Colour::RGB(255, 0, 0).paint(format!("{}: {}", file_name, err)));
+                    Operation::defaults()
+                }
+            }
         } else {
             match File::create(file_name) {
                 Ok(file) => {
@@ -41,15 +133,15 @@ impl Loadable<Vec<Self>> for Operation {
                         &Operation::defaults(),
                     ) {
                         Ok(_) => {
-                            dbgprintln!("Neue Zerfallsreihen wurden erzeugt");
+                            edbgprintln!("Neue Zerfallsreihen wurden erzeugt");
                         }
                         Err(err) => {
-                            dbgprintln!("{}", Colour::RGB(255, 0, 0).paint(err.to_string()));
+                            edbgprintln!("{}", Colour::RGB(255, 0, 0).paint(err.to_string()));
                         }
                     }
                 }
                 Err(err) => {
-                    dbgprintln!("{}", Colour::RGB(255, 0, 0).paint(err.to_string()));
+                    edbgprintln!("{}", Colour::RGB(255, 0, 0).paint(err.to_string()));
                 }
             }
             Operation::defaults()
@@ -62,8 +154,59 @@ impl Operation {
         include!("default_operation.rs")
     }
 
+    /**
+    The name of the operation together with its cost and chance of success
+     */
+    pub fn label(&self) -> String {
+        let mut details: Vec<String> = Vec::with_capacity(2);
+        if let Some(cost) = self.cost.as_ref().filter(|cost| !cost.is_empty()) {
+            details.push(cost.to_string());
+        }
+        if let Some(probability) = self.probability {
+            details.push(format!("{}%", probability));
+        }
+        if details.is_empty() {
+            self.display.clone()
+        } else {
+            format!("{} ({})", self.display, details.join(", "))
+        }
+    }
+
+    /**
+    Checks the preconditions, the required category and that no count becomes negative
+     */
+    pub fn check(&self, state: &State, classifications: &[Classification]) -> Result<(), Unavailable> {
+        if let Some(condition) = self.preconditions.iter().find(|condition| !condition.matches(state)) {
+            return Err(Unavailable::Precondition(*condition));
+        }
+        if !self.categories.is_empty() {
+            let category = Classification::find(classifications, state).map(|rule| &rule.category);
+            if !category.is_some_and(|category| self.categories.contains(category)) {
+                return Err(Unavailable::Category(self.categories.clone()));
+            }
+        }
+        let new = self.apply(*state);
+        if new.protons < 0 || new.electrons < 0 || new.neutrons < 0 {
+            return Err(Unavailable::Negative);
+        }
+        Ok(())
+    }
+
+    /**
+    Rolls whether the operation succeeds, operations without a probability always succeed
+     */
+    pub fn succeeds(&self, rng: &mut impl Rng) -> bool {
+        match self.probability {
+            None => true,
+            Some(probability) => {
+                let uniform = Uniform::new(0f32, 100f32).expect("Failed to create uniform distribution for operations");
+                probability > rng.sample(uniform)
+            }
+        }
+    }
+
     pub fn apply(&self, state: State) -> State {
-        let mut new_state = state.clone();
+        let mut new_state = state;
         match self.electrons {
             None => {}
             Some(val) => {
@@ -84,4 +227,51 @@ impl Operation {
         }
         new_state
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use crate::decay_series::classification::{Classification, Comparison, Condition, Operand, Particle};
+    use crate::decay_series::operation::{Operation, Unavailable};
+    use crate::decay_series::state::State;
+
+    #[test]
+    fn test_check() {
+        let rules = Classification::defaults();
+        let minimum = Condition {
+            left: Operand::Particle(Particle::Protons),
+            op: Comparison::GreaterEqual,
+            right: Operand::Value(3),
+        };
+        let operation = Operation {
+            display: "Alpha".to_string(),
+            protons: Some(-2),
+            neutrons: Some(-2),
+            preconditions: vec![minimum],
+            categories: vec!["Pflanze der 1. Generation".to_string()],
+            ..Default::default()
+        };
+
+        let monster = State { electrons: 2, protons: 2, neutrons: 0 };
+        assert_eq!(operation.check(&monster, &rules), Err(Unavailable::Precondition(minimum)));
+
+        let monster = State { electrons: 4, protons: 4, neutrons: 0 };
+        assert!(matches!(operation.check(&monster, &rules), Err(Unavailable::Category(_))));
+
+        let plant = State { electrons: 1, protons: 4, neutrons: 1 };
+        assert_eq!(operation.check(&plant, &rules), Err(Unavailable::Negative));
+
+        let plant = State { electrons: 1, protons: 4, neutrons: 2 };
+        assert_eq!(operation.check(&plant, &rules), Ok(()));
+    }
+
+    #[test]
+    fn test_probability_range() {
+        let operation = |probability: &str| {
+            serde_yaml::from_str::<Operation>(&format!("{{ display: Alpha, electrons: ~, protons: -2, neutrons: -2, probability: {} }}", probability))
+        };
+        assert_eq!(operation("75").unwrap().probability, Some(75.0));
+        assert!(operation("100").is_ok());
+        assert!(operation("150").is_err());
+        assert!(operation("-1").is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use crate::common::settings_path;
+use crate::decay_series::operation::Cost;
+use crate::decay_series::state::State;
+use crate::decay_series::walk::Step;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::{create_dir_all, read_dir, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+const SESSION_DIR: &str = "zerfallsreihen";
+
+/**
+A decay series which can be saved and resumed in a later game session
+ */
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Session {
+    pub name: String,
+    pub start: State,
+    pub state: State,
+    #[serde(default)]
+    pub history: Vec<Step>,
+    #[serde(default)]
+    pub spent: Cost,
+}
+
+impl Session {
+    pub fn new(start: State) -> Self {
+        Session {
+            name: String::new(),
+            start,
+            state: start,
+            history: vec![],
+            spent: Cost::default(),
+        }
+    }
+
+    pub fn record(&mut self, step: Step) {
+        self.state = step.state;
+        self.history.push(step);
+    }
+
+    fn directory() -> PathBuf {
+        settings_path(SESSION_DIR)
+    }
+
+    fn path(directory: &Path, name: &str) -> PathBuf {
+        let file_name: String = name
+            .trim()
+            .chars()
+            .map(|c| if c == '/' || c == '\\' { '_' } else { c })
+            .collect();
+        directory.join(format!("{}.yaml", file_name))
+    }
+
+    pub fn save(&self) -> Result<PathBuf, Box<dyn Error>> {
+        self.save_in(&Session::directory())
+    }
+
+    fn save_in(&self, directory: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        if self.name.trim().is_empty() {
+            return Err("Die Zerfallsreihe braucht einen Namen".into());
+        }
+        create_dir_all(directory)?;
+        let path = Session::path(directory, &self.name);
+        let writer = BufWriter::new(File::create(&path)?);
+        serde_yaml::to_writer(writer, self)?;
+        Ok(path)
+    }
+
+    /// Same name as given to [`Session::save`], surrounding whitespace is ignored by both
+    pub fn load_named(name: &str) -> Result<Self, Box<dyn Error>> {
+        Session::load_from(&Session::directory(), name)
+    }
+
+    fn load_from(directory: &Path, name: &str) -> Result<Self, Box<dyn Error>> {
+        let reader = BufReader::new(File::open(Session::path(directory, name))?);
+        Ok(serde_yaml::from_reader(reader)?)
+    }
+
+    /**
+    Names of all saved decay series, sorted alphabetically
+     */
+    pub fn list() -> Vec<String> {
+        Session::list_in(&Session::directory())
+    }
+
+    fn list_in(directory: &Path) -> Vec<String> {
+        let mut names: Vec<String> = match read_dir(directory) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|extension| extension == "yaml"))
+                .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().to_string()))
+                .collect(),
+            Err(_) => vec![],
+        };
+        names.sort();
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decay_series::session::Session;
+    use crate::decay_series::state::State;
+    use crate::decay_series::walk::Step;
+    use std::fs::remove_dir_all;
+
+    #[test]
+    fn test_round_trip() {
+        let directory = std::env::temp_dir().join(format!("würfeln-sessions-{}", std::process::id()));
+        let mut session = Session::new(State { electrons: 10, protons: 10, neutrons: 0 });
+        session.name = " Kampagne/Akt 1 ".to_string();
+        session.record(Step {
+            operation: "Alpha".to_string(),
+            state: State { electrons: 10, protons: 8, neutrons: 0 },
+            success: true,
+        });
+
+        let path = session.save_in(&directory).unwrap();
+        assert_eq!(path.file_name().unwrap(), "Kampagne_Akt 1.yaml");
+        assert_eq!(Session::list_in(&directory), vec!["Kampagne_Akt 1"]);
+        // The listed file name, the trimmed and the original name all find the session
+        for name in ["Kampagne_Akt 1", "Kampagne/Akt 1", " Kampagne/Akt 1 "] {
+            assert_eq!(Session::load_from(&directory, name).unwrap(), session);
+        }
+        assert!(Session::load_from(&directory, "Akt 2").is_err());
+
+        session.name = "  ".to_string();
+        assert!(session.save_in(&directory).is_err());
+        remove_dir_all(&directory).unwrap();
+    }
+}
@@ -1,8 +1,13 @@
+use crate::decay_series::classification::{Classification, Particle};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 #[cfg(test)]
 mod tests {
-    use crate::decay_series::state::State;
+    use crate::decay_series::classification::{Classification, Particle};
+    use crate::decay_series::state::{ParseStateError, State};
 
     #[test]
     fn test_validation() {
@@ -24,18 +29,18 @@ mod tests {
             protons: 1,
             neutrons: 0,
         };
-        assert_eq!(test_state.get_description(), "Nicht abgedeckt");
+        assert_eq!(test_state.get_description(&Classification::defaults()), "Nicht abgedeckt");
     }
 
     #[test]
     fn test_froms() {
         assert_eq!(
-            State::from("10;-10;0"),
-            State {
+            State::try_from("10;-10;0"),
+            Ok(State {
                 electrons: 10,
                 protons: 0,
                 neutrons: 0
-            }
+            })
         );
         assert_eq!(
             State::from((10, -10, 0)),
@@ -82,19 +87,51 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
-    fn panic_from() {
-        State::from("22,4,3");
+    fn test_parse_labelled() {
+        assert_eq!(
+            "e=3 p=5 n=2".parse::<State>(),
+            Ok(State {
+                electrons: 3,
+                protons: 5,
+                neutrons: 2
+            })
+        );
+        assert_eq!(
+            "Neutronen = 2; Protonen=5; Elektronen=3".parse::<State>(),
+            Ok(State {
+                electrons: 3,
+                protons: 5,
+                neutrons: 2
+            })
+        );
     }
 
     #[test]
-    #[should_panic]
-    fn panic_from2() {
-        State::from("A;B;C");
+    fn test_parse_errors() {
+        assert_eq!(State::try_from("22,4,3"), Err(ParseStateError::PartCount(1)));
+        assert_eq!(
+            State::try_from("A;B;C"),
+            Err(ParseStateError::InvalidNumber(String::from("A")))
+        );
+        assert_eq!(State::try_from("1;2"), Err(ParseStateError::PartCount(2)));
+        assert_eq!(
+            State::try_from("e=1 x=2 n=3"),
+            Err(ParseStateError::UnknownLabel(String::from("x")))
+        );
+        assert_eq!(
+            State::try_from("e=1 e=2 n=3"),
+            Err(ParseStateError::DuplicateLabel(Particle::Electrons))
+        );
+        assert_eq!(
+            State::try_from("e=1 p=2"),
+            Err(ParseStateError::MissingLabel(Particle::Neutrons))
+        );
+        assert_eq!(State::try_from("e=1 2 3"), Err(ParseStateError::MixedLabels));
+        assert_eq!(State::try_from("  "), Err(ParseStateError::Empty));
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash, Default)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash, Default, Serialize, Deserialize)]
 pub struct State {
     pub electrons: i64,
     pub protons: i64,
@@ -105,8 +142,7 @@ impl Display for State {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{}\nElektronen: {}\nProtonen: {}\nNeutronen: {}",
-            self.get_description(),
+            "Elektronen: {}\nProtonen: {}\nNeutronen: {}",
             self.electrons,
             self.protons,
             self.neutrons
@@ -191,61 +227,131 @@ impl From<(u8, u8, u8)> for State {
     }
 }
 
-impl From<String> for State {
-    fn from(string: String) -> Self {
-        let parts: Vec<i64> = string
-            .split(";")
-            .into_iter()
-            .filter_map(|val| i64::from_str_radix(val, 10).ok())
-            .collect();
-        if parts.len() != 3 {
-            panic!("Invalid number of parts: {}", parts.len());
-        }
-        State {
-            electrons: parts[0],
-            protons: parts[1],
-            neutrons: parts[2],
+/**
+Why a string could not be parsed into a [`State`]
+ */
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum ParseStateError {
+    Empty,
+    PartCount(usize),
+    InvalidNumber(String),
+    UnknownLabel(String),
+    DuplicateLabel(Particle),
+    MissingLabel(Particle),
+    MixedLabels,
+}
+
+impl Display for ParseStateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseStateError::Empty => write!(f, "Bitte etwas eingeben"),
+            ParseStateError::PartCount(count) => write!(
+                f,
+                "Es werden genau drei Werte (Elektronen;Protonen;Neutronen) erwartet, gefunden: {}",
+                count
+            ),
+            ParseStateError::InvalidNumber(part) => write!(f, "\"{}\" ist keine Ganzzahl", part),
+            ParseStateError::UnknownLabel(label) => write!(f, "Unbekannte Bezeichnung \"{}\", erlaubt sind e, p und n", label),
+            ParseStateError::DuplicateLabel(particle) => write!(f, "{} wurden mehrfach angegeben", particle),
+            ParseStateError::MissingLabel(particle) => write!(f, "{} fehlen", particle),
+            ParseStateError::MixedLabels => write!(f, "Entweder alle Werte oder keinen Wert bezeichnen"),
         }
-            .validate()
     }
 }
 
-impl From<&str> for State {
-    fn from(string: &str) -> Self {
-        let parts: Vec<i64> = string
-            .split(";")
-            .into_iter()
-            .filter_map(|val| i64::from_str_radix(val, 10).ok())
+impl Error for ParseStateError {}
+
+fn parse_count(part: &str) -> Result<i64, ParseStateError> {
+    part.trim()
+        .parse::<i64>()
+        .map_err(|_| ParseStateError::InvalidNumber(part.trim().to_string()))
+}
+
+fn parse_label(label: &str) -> Result<Particle, ParseStateError> {
+    match label.trim().to_lowercase().as_str() {
+        "e" | "electrons" | "elektronen" => Ok(Particle::Electrons),
+        "p" | "protons" | "protonen" => Ok(Particle::Protons),
+        "n" | "neutrons" | "neutronen" => Ok(Particle::Neutrons),
+        _ => Err(ParseStateError::UnknownLabel(label.trim().to_string())),
+    }
+}
+
+/**
+Parses either `electrons;protons;neutrons` or labelled values like `e=3 p=5 n=2` in any order.
+Negative counts are clamped to 0 like every other way of creating a state
+ */
+impl FromStr for State {
+    type Err = ParseStateError;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        // Allows "e = 3" as well as "e=3"
+        let normalized = string.split('=').map(str::trim).collect::<Vec<_>>().join("=");
+        let parts: Vec<&str> = normalized
+            .split(|c: char| c == ';' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
             .collect();
-        if parts.len() != 3 {
-            panic!("Invalid number of parts: {}", parts.len());
+        if parts.is_empty() {
+            return Err(ParseStateError::Empty);
         }
-        State {
-            electrons: parts[0],
-            protons: parts[1],
-            neutrons: parts[2],
+
+        let labelled = parts.iter().filter(|part| part.contains('=')).count();
+        if labelled == 0 {
+            if parts.len() != 3 {
+                return Err(ParseStateError::PartCount(parts.len()));
+            }
+            return Ok(State::from((
+                parse_count(parts[0])?,
+                parse_count(parts[1])?,
+                parse_count(parts[2])?,
+            )));
         }
-            .validate()
+        if labelled != parts.len() {
+            return Err(ParseStateError::MixedLabels);
+        }
+
+        let mut counts: [Option<i64>; 3] = [None; 3];
+        for part in parts {
+            let (label, value) = part.split_once('=').unwrap_or((part, ""));
+            let particle = parse_label(label)?;
+            let slot = &mut counts[particle as usize];
+            if slot.is_some() {
+                return Err(ParseStateError::DuplicateLabel(particle));
+            }
+            *slot = Some(parse_count(value)?);
+        }
+        let [electrons, protons, neutrons] = counts;
+        Ok(State::from((
+            electrons.ok_or(ParseStateError::MissingLabel(Particle::Electrons))?,
+            protons.ok_or(ParseStateError::MissingLabel(Particle::Protons))?,
+            neutrons.ok_or(ParseStateError::MissingLabel(Particle::Neutrons))?,
+        )))
+    }
+}
+
+impl TryFrom<&str> for State {
+    type Error = ParseStateError;
+
+    fn try_from(string: &str) -> Result<Self, Self::Error> {
+        string.parse()
+    }
+}
+
+impl TryFrom<String> for State {
+    type Error = ParseStateError;
+
+    fn try_from(string: String) -> Result<Self, Self::Error> {
+        string.parse()
     }
 }
 
 impl State {
-    pub fn get_description(&self) -> String {
-        if self.neutrons == 0 && self.electrons == self.protons {
-            format!("Monster Nr. {}", self.protons)
-        } else if self.neutrons != 0 {
-            if self.protons == self.electrons {
-                format!("Pflanze der 2. Generation Nr. {}", self.protons)
-            } else if self.protons < self.electrons {
-                format!("Pflanze der 3. Generation Nr. {}", self.protons)
-            } else if self.electrons < self.protons {
-                format!("Pflanze der 1. Generation Nr. {}", self.protons)
-            } else {
-                String::from("Nicht abgedeckt")
-            }
-        } else {
-            String::from("Nicht abgedeckt")
-        }
+    /**
+    Describes the state with the first matching classification rule
+     */
+    pub fn get_description(&self, rules: &[Classification]) -> String {
+        Classification::find(rules, self)
+            .map(|rule| rule.render(self))
+            .unwrap_or_else(|| String::from("Nicht abgedeckt"))
     }
 
     fn validate(&self) -> Self {
@@ -0,0 +1,124 @@
+use crate::decay_series::classification::Classification;
+use crate::decay_series::operation::Operation;
+use crate::decay_series::state::State;
+use rand::distr::Uniform;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/**
+One step of a decay series. `state` is the state after the operation was tried
+ */
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Step {
+    pub operation: String,
+    pub state: State,
+    pub success: bool,
+}
+
+/// Most steps of one random walk, operations like "Beta hin" and "Beta rück" undo each other and never reach a dead end
+pub const MAX_STEPS: usize = 1000;
+
+/**
+Creates a state with every count between 0 and `max`
+ */
+pub fn random_state(max: i64, rng: &mut impl Rng) -> State {
+    let uniform = Uniform::new_inclusive(0, max.max(0)).expect("Failed to create uniform distribution for states");
+    State::from((rng.sample(uniform), rng.sample(uniform), rng.sample(uniform)))
+}
+
+/**
+Applies up to `steps`, at most [`MAX_STEPS`], randomly chosen operations which are available in the current state.
+Ends early if no operation is available anymore
+ */
+pub fn random_walk(
+    start: State,
+    operations: &[Operation],
+    classifications: &[Classification],
+    steps: usize,
+    rng: &mut impl Rng,
+) -> Vec<Step> {
+    let mut state = start;
+    let steps = steps.min(MAX_STEPS);
+    let mut walk = Vec::with_capacity(steps);
+    for _ in 0..steps {
+        let available: Vec<&Operation> = operations
+            .iter()
+            .filter(|operation| operation.check(&state, classifications).is_ok())
+            .collect();
+        if available.is_empty() {
+            break;
+        }
+        let uniform = Uniform::new(0, available.len()).expect("Failed to create uniform distribution for operations");
+        let operation = available[rng.sample(uniform)];
+        let success = operation.succeeds(rng);
+        if success {
+            state = operation.apply(state);
+        }
+        walk.push(Step {
+            operation: operation.display.clone(),
+            state,
+            success,
+        });
+    }
+    walk
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decay_series::classification::{Classification, Comparison, Condition, Operand, Particle};
+    use crate::decay_series::operation::Operation;
+    use crate::decay_series::state::State;
+    use crate::decay_series::walk::{random_walk, MAX_STEPS};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_random_walk_ends_at_dead_end() {
+        let operations = [
+            Operation {
+                display: "Alpha".to_string(),
+                protons: Some(-2),
+                neutrons: Some(-2),
+                ..Default::default()
+            },
+            Operation {
+                display: "Nie".to_string(),
+                electrons: Some(1),
+                preconditions: vec![Condition {
+                    left: Operand::Particle(Particle::Electrons),
+                    op: Comparison::Equal,
+                    right: Operand::Value(100),
+                }],
+                ..Default::default()
+            },
+        ];
+        let rules = Classification::defaults();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let walk = random_walk(State { electrons: 5, protons: 4, neutrons: 4 }, &operations, &rules, usize::MAX, &mut rng);
+        assert_eq!(walk.len(), 2);
+        assert!(walk.iter().all(|step| step.operation == "Alpha" && step.success));
+        assert_eq!(walk[1].state, State { electrons: 5, protons: 0, neutrons: 0 });
+    }
+
+    #[test]
+    fn test_random_walk_is_capped() {
+        let operations = [
+            Operation {
+                display: "Hin".to_string(),
+                electrons: Some(1),
+                ..Default::default()
+            },
+            Operation {
+                display: "Rück".to_string(),
+                electrons: Some(-1),
+                ..Default::default()
+            },
+        ];
+        let rules = Classification::defaults();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let walk = random_walk(State { electrons: 50, protons: 50, neutrons: 50 }, &operations, &rules, usize::MAX, &mut rng);
+        assert_eq!(walk.len(), MAX_STEPS);
+    }
+}
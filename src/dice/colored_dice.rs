@@ -1,9 +1,10 @@
 use ansi_term::Colour;
 use crate::common::{settings_path, Loadable, Rollable};
-use crate::dbgprintln;
+use crate::edbgprintln;
 use rand::Rng;
 use rand::distr::Uniform;
 use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
 use std::fs::File;
 use std::io::{BufReader, BufWriter};
 use std::path::Path;
@@ -44,6 +45,53 @@ impl From<RawColoredDice> for ColoredDice {
 	}
 }
 
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DiceResult {
+	pub name: String,
+	pub short: char,
+	/// Number of dice of this color
+	pub amount: usize,
+	pub result: u64,
+}
+
+/**
+Machine readable result, `value` is the total times 10
+ */
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ColoredReport {
+	pub dice: Vec<DiceResult>,
+	pub total: u64,
+	pub value: u64,
+}
+
+impl ColoredReport {
+	/// Takes the dice, how many of them were rolled and their sum
+	pub fn new(results: &[(&ColoredDice, usize, u64)]) -> Self {
+		let total = results.iter().map(|(_, _, result)| result).sum();
+		ColoredReport {
+			dice: results.iter()
+				.map(|(dice, amount, result)| DiceResult {
+					name: dice.long.clone(),
+					short: dice.short,
+					amount: *amount,
+					result: *result,
+				})
+				.collect(),
+			total,
+			value: total * 10,
+		}
+	}
+}
+
+impl Display for ColoredReport {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		for dice in &self.dice {
+			writeln!(f, "{}: {}", dice.name, dice.result)?;
+		}
+		write!(f, "Insgesamt: {} ({})", self.total, self.value)
+	}
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ColoredDices {
 	pub dices: Vec<ColoredDice>,
@@ -99,7 +147,7 @@ impl Loadable<Self> for ColoredDices {
 			let file = File::open(file_name).unwrap();
 			let buf_reader = BufReader::new(file);
 			serde_yaml::from_reader::<BufReader<File>, ColoredDices>(buf_reader)
-				.unwrap_or(ColoredDices::default())
+				.unwrap_or_default()
 		} else {
 			match File::create(file_name) {
 				Ok(file) => {
@@ -109,15 +157,15 @@ impl Loadable<Self> for ColoredDices {
 						&ColoredDices::default(),
 					) {
 						Ok(_) => {
-							dbgprintln!("Neue Farbiger Würfel wurden erzeugt");
+							edbgprintln!("Neue Farbiger Würfel wurden erzeugt");
 						}
 						Err(err) => {
-							dbgprintln!("{}", Colour::RGB(255, 0, 0).paint(err.to_string()));
+							edbgprintln!("{}", Colour::RGB(255, 0, 0).paint(err.to_string()));
 						}
 					}
 				}
 				Err(err) => {
-					dbgprintln!("{}", Colour::RGB(255, 0, 0).paint(err.to_string()));
+					edbgprintln!("{}", Colour::RGB(255, 0, 0).paint(err.to_string()));
 				}
 			}
 			ColoredDices::default()
@@ -125,11 +173,120 @@ impl Loadable<Self> for ColoredDices {
 	}
 }
 
+impl ColoredDice {
+	/// Expected result of one roll
+	pub fn mean(&self) -> f64 {
+		self.sites.iter().map(|site| *site as f64).sum::<f64>() / self.sites.len() as f64
+	}
+
+	/// Variance of the result of one roll
+	pub fn variance(&self) -> f64 {
+		let mean = self.mean();
+		self.sites.iter().map(|site| (*site as f64 - mean).powi(2)).sum::<f64>() / self.sites.len() as f64
+	}
+
+	/// Sum of physical dice, every face has to exist on this die
+	pub fn sum_faces(&self, faces: &[u8]) -> Result<u64, String> {
+		match faces.iter().find(|face| !self.sites.contains(face)) {
+			Some(face) => Err(format!("{} hat keine Seite {}", self.long, face)),
+			None => Ok(faces.iter().map(|face| *face as u64).sum()),
+		}
+	}
+}
+
 impl Rollable<u8> for ColoredDice {
     fn roll(&self, rng: &mut impl Rng) -> u8 {
         if self.sites.is_empty() {
             return 0;
         }
-        self.sites[rng.sample(&self.range)]
+        self.sites[rng.sample(self.range)]
     }
 }
+
+impl ColoredDices {
+	pub fn find(&self, short: char) -> Option<&ColoredDice> {
+		self.dices.iter()
+			.find(|dice| dice.short == short)
+			.or_else(|| self.dices.iter().find(|dice| dice.short.eq_ignore_ascii_case(&short)))
+	}
+
+	/// Parses a selection like `3r2g` into the amount of each dice, a dice without amount is rolled once
+	pub fn parse_selection(&self, input: &str) -> Result<Vec<(&ColoredDice, usize)>, String> {
+		let mut selection: Vec<(&ColoredDice, usize)> = vec![];
+		let mut digits = String::new();
+		for c in input.chars().filter(|c| !c.is_whitespace() && *c != ',') {
+			if c.is_ascii_digit() {
+				digits.push(c);
+				continue;
+			}
+			let dice = self.find(c).ok_or_else(|| format!("Unbekannter Würfel: {}", c))?;
+			let amount = if digits.is_empty() {
+				1
+			} else {
+				digits.parse::<usize>().map_err(|err| format!("Ungültige Anzahl {}: {}", digits, err))?
+			};
+			digits.clear();
+			match selection.iter_mut().find(|(selected, _)| selected.short == dice.short) {
+				Some((_, total)) => *total += amount,
+				None => selection.push((dice, amount)),
+			}
+		}
+		if !digits.is_empty() {
+			return Err(format!("Nach {} fehlt der Würfel", digits));
+		}
+		if selection.is_empty() {
+			return Err("Keine Würfel angegeben".to_string());
+		}
+		Ok(selection)
+	}
+
+	/// Rolls the given amount of each dice and returns the amount and sum per dice
+	pub fn roll_selection<'a>(selection: &[(&'a ColoredDice, usize)], rng: &mut impl Rng) -> Vec<(&'a ColoredDice, usize, u64)> {
+		selection.iter()
+			.map(|(dice, amount)| (*dice, *amount, (0..*amount).map(|_| dice.roll(rng) as u64).sum()))
+			.collect()
+	}
+
+	/// Splits `value` into as few dice as possible, starting with the most valuable one
+	pub fn split_value(&self, value: usize) -> Vec<(&ColoredDice, usize)> {
+		let mut sorted: Vec<&ColoredDice> = self.dices.iter().filter(|dice| dice.value > 0).collect();
+		sorted.sort_by(|a, b| a.value.cmp(&b.value).reverse());
+		let mut remaining = value;
+		let mut selection = Vec::with_capacity(sorted.len());
+		for dice in sorted {
+			selection.push((dice, remaining / dice.value as usize));
+			remaining %= dice.value as usize;
+		}
+		selection
+	}
+
+	/// Rolls the dice of [`ColoredDices::split_value`]
+	pub fn roll_value(&self, value: usize, rng: &mut impl Rng) -> Vec<(&ColoredDice, usize, u64)> {
+		ColoredDices::roll_selection(&self.split_value(value), rng)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::dice::colored_dice::ColoredDices;
+
+	#[test]
+	fn test_parse_selection() {
+		let dices = ColoredDices::default();
+		let selection = dices.parse_selection("3r2g s 1R").unwrap();
+		let amounts: Vec<(char, usize)> = selection.iter().map(|(dice, amount)| (dice.short, *amount)).collect();
+		assert_eq!(amounts, vec![('r', 4), ('g', 2), ('s', 1)]);
+		assert!(dices.parse_selection("3x").is_err());
+		assert!(dices.parse_selection("3r2").is_err());
+		assert!(dices.parse_selection("").is_err());
+	}
+
+	#[test]
+	fn test_sum_faces() {
+		let dices = ColoredDices::default();
+		let rosa = dices.find('r').unwrap();
+		assert_eq!(rosa.sum_faces(&[0, 1, 2, 2]), Ok(5));
+		assert_eq!(rosa.sum_faces(&[]), Ok(0));
+		assert!(rosa.sum_faces(&[1, 3]).is_err());
+	}
+}
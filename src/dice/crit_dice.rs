@@ -43,8 +43,7 @@ pub struct Level {
 
 impl Display for Level {
 	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-		let up = self.upper
-			.and_then(|val| Some(val.to_string()))
+		let up = self.upper.map(|val| val.to_string())
 			.unwrap_or_else(|| "Keine Begrenzung".to_string());
 		write!(f, "Level {} - {}", self.lower, up)
 	}
@@ -106,11 +105,16 @@ impl Level {
 		let random_value = rng.sample(uniform);
 		self.percentage > random_value
 	}
+
+	/// Same chance as [`Level::works`] with a physical W100
+	fn works_with(&self, face: u8) -> bool {
+		self.percentage >= face as f32
+	}
 }
 
 impl Rollable<u8> for CritDice {
     fn roll(&self, rng: &mut impl Rng) -> u8 {
-		let random_value = rng.sample(&self.distribution);
+		let random_value = rng.sample(self.distribution);
         self.values[random_value]
     }
 }
@@ -148,14 +152,32 @@ impl Loadable<CritDices> for CritDices {
 	}
 }
 
+/**
+Result of a crit roll
+ */
+#[derive(PartialEq, Debug, Clone, Serialize)]
+pub struct CritResult {
+	/// Levels which have a crit
+	pub levels: Vec<Level>,
+	/// How many "S" were rolled
+	pub s: usize,
+	pub lightning: u64,
+}
+
+impl Display for CritResult {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		writeln!(f, "Folgende Level haben crits:")?;
+		for level in &self.levels {
+			writeln!(f, "Level: {}", level)?;
+		}
+		writeln!(f, "S: {}", self.s)?;
+		write!(f, "Blitze: {}", self.lightning)
+	}
+}
+
 impl CritDices {
-	pub fn roll(&self, value: i16, rng: &mut impl Rng) {
-		let levels: Vec<(&Level, bool)> = self
-			.level
-			.iter()
-			.map(|x| (x, x.works(rng)))
-			.filter(|x| x.1)
-			.collect();
+	/// The dice which are rolled for `value`
+	pub fn dice_for(&self, value: i16) -> Vec<&CritDice> {
 		let mut stack: Vec<&CritDice> = Vec::with_capacity(10);
 		let mut counter: i16 = value;
 		while counter != 0 {
@@ -167,17 +189,97 @@ impl CritDices {
 				}
 			}
 		}
-		let results: Vec<u8> = stack.iter_mut().map(|x| x.roll(rng)).collect();
+		stack
+	}
+
+	fn tally(&self, levels: Vec<Level>, results: Vec<u8>) -> CritResult {
 		// How many "S" where found in the "rolled" dices
 		let s_counter = results.iter().filter(|x| **x == self.s).count();
-		let counter: u8 = results.into_iter().filter(|x| *x != self.s).sum();
-		dbgprintln!("Folgende Level haben crits:");
-		levels.iter().for_each(|x| {
-			if x.1 {
-				dbgprintln!("Level: {}", x.0);
-			}
-		});
-		dbgprintln!("S: {}", s_counter);
-		dbgprintln!("Blitze: {}", counter);
+		let counter: u64 = results.into_iter().filter(|x| *x != self.s).map(u64::from).sum();
+		CritResult {
+			levels,
+			s: s_counter,
+			lightning: counter,
+		}
+	}
+
+	pub fn evaluate(&self, value: i16, rng: &mut impl Rng) -> CritResult {
+		let levels: Vec<Level> = self
+			.level
+			.iter()
+			.filter(|x| x.works(rng))
+			.copied()
+			.collect();
+		let results: Vec<u8> = self.dice_for(value).iter().map(|x| x.roll(rng)).collect();
+		self.tally(levels, results)
+	}
+
+	/// Physical dice decide the levels with one W100 per level
+	pub fn check_level_faces(&self, level_faces: &[u8]) -> Result<(), String> {
+		if level_faces.len() != self.level.len() {
+			return Err(format!("Es werden {} W100 gebraucht, einer pro Level", self.level.len()));
+		}
+		match level_faces.iter().find(|face| **face == 0 || **face > 100) {
+			Some(face) => Err(format!("Ein W100 hat keine Seite {}", face)),
+			None => Ok(()),
+		}
+	}
+
+	/// Evaluates physical dice, `faces` has one face per die of [`CritDices::dice_for`]
+	pub fn evaluate_faces(&self, value: i16, level_faces: &[u8], faces: &[u8]) -> Result<CritResult, String> {
+		self.check_level_faces(level_faces)?;
+		let dice = self.dice_for(value);
+		if faces.len() != dice.len() {
+			return Err(format!("Es werden {} Crit Würfel gebraucht", dice.len()));
+		}
+		if let Some((dice, face)) = dice.iter().zip(faces).find(|(dice, face)| !dice.values.contains(face)) {
+			return Err(format!("Der Crit Würfel mit Wert {} hat keine Seite {}", dice.value, face));
+		}
+		let levels = self.level
+			.iter()
+			.zip(level_faces)
+			.filter(|(level, face)| level.works_with(**face))
+			.map(|(level, _)| *level)
+			.collect();
+		Ok(self.tally(levels, faces.to_vec()))
+	}
+
+	pub fn roll(&self, value: i16, rng: &mut impl Rng) -> CritResult {
+		let result = self.evaluate(value, rng);
+		dbgprintln!("{}", result);
+		result
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::dice::crit_dice::CritDices;
+
+	#[test]
+	fn test_check_level_faces() {
+		let crits = CritDices::default();
+		assert!(crits.check_level_faces(&[1, 50, 100]).is_ok());
+		assert!(crits.check_level_faces(&[1, 50]).is_err());
+		assert!(crits.check_level_faces(&[0, 50, 100]).is_err());
+		assert!(crits.check_level_faces(&[1, 50, 101]).is_err());
+	}
+
+	#[test]
+	fn test_evaluate_faces() {
+		let crits = CritDices::default();
+		let dice = crits.dice_for(3).len();
+		let result = crits.evaluate_faces(3, &[40, 70, 90], &vec![0; dice]).unwrap();
+		let lower: Vec<u8> = result.levels.iter().map(|level| level.lower).collect();
+		assert_eq!(lower, vec![0]);
+		assert_eq!((result.s, result.lightning), (0, 0));
+
+		let result = crits.evaluate_faces(3, &[100, 50, 1], &vec![1; dice]).unwrap();
+		let lower: Vec<u8> = result.levels.iter().map(|level| level.lower).collect();
+		assert_eq!(lower, vec![10, 20]);
+		assert_eq!(result.lightning, dice as u64);
+
+		assert!(crits.evaluate_faces(3, &[1, 1], &vec![0; dice]).is_err());
+		assert!(crits.evaluate_faces(3, &[1, 1, 1], &vec![0; dice + 1]).is_err());
+		assert!(crits.evaluate_faces(3, &[1, 1, 1], &vec![2; dice]).is_err());
 	}
 }
@@ -1,3 +1,13 @@
 pub mod normal_dice;
 pub mod colored_dice;
-pub mod crit_dice;
\ No newline at end of file
+pub mod crit_dice;
+
+/**
+Parses faces of physical dice separated by spaces or commas, e.g. `1 4 6`
+ */
+pub fn parse_faces(input: &str) -> Result<Vec<u8>, String> {
+	input.split(|c: char| c.is_whitespace() || c == ',')
+		.filter(|face| !face.is_empty())
+		.map(|face| face.parse::<u8>().map_err(|_| format!("Keine gültige Augenzahl: {}", face)))
+		.collect()
+}
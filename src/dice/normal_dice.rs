@@ -1,4 +1,5 @@
 use std::error::Error;
+use std::fmt::{Display, Formatter};
 use ansi_term::Colour;
 use crate::common::{settings_path, Loadable};
 use std::fs::{File, OpenOptions};
@@ -8,7 +9,7 @@ use std::vec::Vec;
 use rand::Rng;
 use rand::distr::Uniform;
 use serde::{Deserialize, Serialize};
-use crate::dbgprintln;
+use crate::{dbgprintln, edbgprintln};
 
 const NORMAL_DICES_FILE: &str = "normal.yaml";
 
@@ -22,7 +23,10 @@ pub struct Results {
 	count: u64,
 }
 
-fn calculate_variance(counts: &Vec<u64>, total_samples: u32, sides: u32) -> f64 {
+/**
+Mean squared distance of the counts of each side to the count a fair die would have
+ */
+pub fn calculate_variance(counts: &[u64], total_samples: u32, sides: u32) -> f64 {
 	let mean = total_samples as f64 / sides as f64;
 	let variance: f64 = counts.iter()
 		.map(|&count| {
@@ -33,7 +37,96 @@ fn calculate_variance(counts: &Vec<u64>, total_samples: u32, sides: u32) -> f64
 	variance
 }
 
+/**
+Summary of a roll with d6, every field counts dice
+ */
+#[derive(Debug, PartialEq, Serialize)]
+pub struct D6Summary {
+	/// 1
+	pub failures: u64,
+	/// 1 + 2
+	pub failures_improvised: u64,
+	/// 1 + 2 + 3
+	pub failures_bad_luck_phial: u64,
+	/// 3 + 4 + 5 + 6
+	pub successes_wealth_phial: u64,
+	/// 4 + 5 + 6
+	pub successes_luck_phial: u64,
+	/// 5 + 6
+	pub successes: u64,
+}
+
+/**
+Machine readable form of [`Results`]
+ */
+#[derive(Debug, PartialEq, Serialize)]
+pub struct RollReport {
+	pub amount: u64,
+	pub sides: u8,
+	/// Every single result in the order they were rolled, only known with the old style
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub values: Option<Vec<u8>>,
+	/// How often each side was rolled, the first entry counts the ones
+	pub counts: Vec<u64>,
+	pub sum: u64,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub d6: Option<D6Summary>,
+}
+
+impl Display for RollReport {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}W{}", self.amount, self.sides)?;
+		if let Some(values) = &self.values {
+			let values: Vec<String> = values.iter().map(u8::to_string).collect();
+			write!(f, ": {}", values.join(" "))?;
+		}
+		write!(f, "\nSumme: {}", self.sum)?;
+		if let Some(d6) = &self.d6 {
+			write!(f, "\nErfolge: {}, Misserfolge: {}", d6.successes, d6.failures)?;
+		}
+		Ok(())
+	}
+}
+
 impl Results {
+	/// How often each side was rolled, the first entry counts the ones
+	pub fn counts(&self) -> Vec<u64> {
+		if let Some(counts) = &self.counts {
+			return counts.clone();
+		}
+		let mut counts = vec![0u64; self.sides as usize];
+		for &value in self.data.iter().flatten() {
+			if value > 0 && value <= self.sides {
+				counts[(value - 1) as usize] += 1;
+			}
+		}
+		counts
+	}
+
+	pub fn report(&self) -> RollReport {
+		let counts = self.counts();
+		let sum = counts.iter()
+			.enumerate()
+			.map(|(index, &count)| (index + 1) as u64 * count)
+			.sum();
+		let d6 = (self.sides == 6).then(|| D6Summary {
+			failures: counts[0],
+			failures_improvised: counts[0] + counts[1],
+			failures_bad_luck_phial: counts[0] + counts[1] + counts[2],
+			successes_wealth_phial: counts[2] + counts[3] + counts[4] + counts[5],
+			successes_luck_phial: counts[3] + counts[4] + counts[5],
+			successes: counts[4] + counts[5],
+		});
+		RollReport {
+			amount: self.count,
+			sides: self.sides,
+			values: self.data.clone(),
+			counts,
+			sum,
+			d6,
+		}
+	}
+
 	pub fn print_results(&self, old_style: bool, no_summary: bool) {
 		println!("\n");
 		if let Some(counts) = &self.counts {
@@ -143,35 +236,67 @@ impl Results {
 			if let Some(counts) = &self.counts {
 				dbgprintln!("Varianz: {}", calculate_variance(counts, counts.iter().sum::<u64>() as u32, self.sides as u32));
 			} else if let Some(data) = &self.data {
-				let mut counts = vec![0u64; self.sides as usize];
-				for &value in data {
-					if value > 0 && value <= self.sides {
-						counts[(value - 1) as usize] += 1;
-					}
-				}
-				dbgprintln!("Varianz: {}", calculate_variance(&counts, data.len() as u32, self.sides as u32));
+				dbgprintln!("Varianz: {}", calculate_variance(&self.counts(), data.len() as u32, self.sides as u32));
 			}
 		}
 
 	}
 }
 
+/**
+Parses an expression like `5d6`, `W20` or `d100` into the amount of dice and their sides
+ */
+pub fn parse_expression(input: &str) -> Result<(usize, u8), String> {
+	let lowercase = input.trim().to_lowercase();
+	let (amount, sides) = lowercase
+		.split_once(['d', 'w'])
+		.ok_or_else(|| format!("Ungültiger Ausdruck: {}, erwartet z.B. 5d6", input.trim()))?;
+	let amount = if amount.trim().is_empty() {
+		1
+	} else {
+		amount.trim().parse::<usize>().map_err(|err| format!("Ungültige Anzahl {}: {}", amount.trim(), err))?
+	};
+	let sides = sides.trim().parse::<u8>().map_err(|err| format!("Ungültige Seitenanzahl {}: {}", sides.trim(), err))?;
+	if sides == 0 {
+		return Err("Ein Würfel braucht mindestens eine Seite".to_string());
+	}
+	Ok((amount, sides))
+}
+
+/**
+Results of physical dice, every face has to exist on a die with `sides` sides
+ */
+pub fn from_faces(sides: u8, faces: Vec<u8>) -> Result<Results, String> {
+	if faces.is_empty() {
+		return Err("Es wurde keine Augenzahl eingegeben".to_string());
+	}
+	if let Some(face) = faces.iter().find(|face| **face == 0 || **face > sides) {
+		return Err(format!("Ein W{} hat keine Seite {}", sides, face));
+	}
+	Ok(Results {
+		counts: None,
+		count: faces.len() as u64,
+		data: Some(faces),
+		sides,
+	})
+}
+
 pub fn roll(amount: usize, sides: u8, old_style: bool, rng: &mut impl Rng) -> Results {
 	if old_style {
 		let distribution = Uniform::<u8>::new_inclusive(1, sides).expect("Failed to create uniform distribution");
 		Results {
 			counts: None,
-			data: Some((0..amount).map(|_| rng.sample(&distribution)).collect()),
+			data: Some((0..amount).map(|_| rng.sample(distribution)).collect()),
 			sides,
 			count: amount as u64,
 		}
 	} else {
-		// Pre-allocate space for each side (1-indexed for convenience)
-		let mut counts = vec![0u64; (sides + 1) as usize];
-		let die_range = Uniform::new_inclusive(1, sides as usize).expect("Failed to create uniform distribution for dice rolls");
+		// Pre-allocate space for each side, index 0 counts the ones
+		let mut counts = vec![0u64; sides as usize];
+		let die_range = Uniform::new(0, sides as usize).expect("Failed to create uniform distribution for dice rolls");
 		for _ in 0..amount {
 			let result = rng.sample(die_range);
-			// We use unchecked access because the distribution guarantees 1..=sides
+			// We use unchecked access because the distribution guarantees 0..sides
 			unsafe {
 				*counts.get_unchecked_mut(result) += 1;
 			}
@@ -210,12 +335,12 @@ impl Loadable<Self> for Dices {
 		let path = Path::new(&file_name);
 		Dices::load_from_path(path)
 			.unwrap_or_else(|e| {
-				dbgprintln!("{}", Colour::RGB(255, 0, 0).paint(format!("Error loading dices: {}. Attempting to create default.", e)));
+				edbgprintln!("{}", Colour::RGB(255, 0, 0).paint(format!("Error loading dices: {}. Attempting to create default.", e)));
 				let default_dices = Dices::default();
 				let save_result = default_dices.save_to_path(path);
 				match save_result {
-					Ok(_) => dbgprintln!("New default dices created at: {}", path.display()),
-					Err(err) => dbgprintln!("{}", Colour::RGB(255, 0, 0).paint(format!("Error creating or saving default dices: {}", err)))
+					Ok(_) => edbgprintln!("New default dices created at: {}", path.display()),
+					Err(err) => edbgprintln!("{}", Colour::RGB(255, 0, 0).paint(format!("Error creating or saving default dices: {}", err)))
 				}
 				default_dices
 			})
@@ -249,3 +374,47 @@ impl Dices {
 		Ok(())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use crate::dice::normal_dice::{from_faces, parse_expression, roll};
+	use rand::rngs::StdRng;
+	use rand::SeedableRng;
+
+	#[test]
+	fn test_parse_expression() {
+		assert_eq!(parse_expression("5d6"), Ok((5, 6)));
+		assert_eq!(parse_expression(" W20 "), Ok((1, 20)));
+		assert_eq!(parse_expression("2D100"), Ok((2, 100)));
+		assert!(parse_expression("5").is_err());
+		assert!(parse_expression("5d0").is_err());
+		assert!(parse_expression("xd6").is_err());
+		assert!(parse_expression("5d300").is_err());
+	}
+
+	#[test]
+	fn test_from_faces() {
+		let report = from_faces(6, vec![1, 6, 6]).unwrap().report();
+		assert_eq!(report.sum, 13);
+		assert_eq!(report.counts, vec![1, 0, 0, 0, 0, 2]);
+		assert!(from_faces(6, vec![7]).is_err());
+		assert!(from_faces(6, vec![0]).is_err());
+		assert!(from_faces(6, vec![]).is_err());
+	}
+
+	#[test]
+	fn test_report_counts() {
+		let mut rng = StdRng::seed_from_u64(0);
+		let old = roll(50, 6, true, &mut rng).report();
+		let values = old.values.clone().unwrap();
+		assert_eq!(old.counts.len(), 6);
+		assert_eq!(old.counts.iter().sum::<u64>(), 50);
+		assert_eq!(old.sum, values.iter().map(|value| *value as u64).sum::<u64>());
+		assert_eq!(old.d6.unwrap().failures, values.iter().filter(|value| **value == 1).count() as u64);
+
+		let new = roll(50, 20, false, &mut rng).report();
+		assert_eq!(new.counts.len(), 20);
+		assert_eq!(new.counts.iter().sum::<u64>(), 50);
+		assert!(new.values.is_none() && new.d6.is_none());
+	}
+}
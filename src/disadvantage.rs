@@ -1,5 +1,6 @@
 use crate::common::{settings_path, Loadable};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
 use std::fs::{File, OpenOptions};
 use std::io::{BufReader, BufWriter};
@@ -7,10 +8,90 @@ use std::path::Path;
 use rand::distr::Uniform;
 use rand::Rng;
 
+/**
+Range of IP a disadvantage gives, depending on how severe it is
+ */
+#[derive(Eq, PartialEq, Ord, PartialOrd, Deserialize, Serialize, Default, Hash, Debug, Clone, Copy)]
+pub struct IpRange {
+	pub min: u32,
+	pub max: u32,
+}
+
+impl std::ops::Add for IpRange {
+	type Output = IpRange;
+
+	fn add(self, other: IpRange) -> IpRange {
+		IpRange {
+			min: self.min + other.min,
+			max: self.max + other.max,
+		}
+	}
+}
+
+impl Display for IpRange {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		if self.min == self.max {
+			write!(f, "{} IP", self.min)
+		} else {
+			write!(f, "{} bis {} IP", self.min, self.max)
+		}
+	}
+}
+
 #[derive(Eq, PartialEq, Ord, PartialOrd, Deserialize, Serialize, Default, Hash, Debug, Clone)]
 pub struct Disadvantage {
 	name: String,
 	description: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	ip: Option<IpRange>,
+	/// Attributes which are set to a fixed value by the disadvantage
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	fixes: BTreeMap<String, i64>,
+	/// Minimal attribute values the character needs to choose the disadvantage
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	requires: BTreeMap<String, i64>,
+	/// Names of disadvantages which can not be combined with this one
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	excludes: Vec<String>,
+	/// Disadvantages sharing a group are mutually exclusive
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	groups: Vec<String>,
+}
+
+/**
+What is known about a character when rolling disadvantages for it.
+Attributes which are not known are not checked
+ */
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Character {
+	pub attributes: BTreeMap<String, i64>,
+	/// Names of the disadvantages the character already has
+	pub disadvantages: Vec<String>,
+}
+
+/**
+Machine readable result of drawing several disadvantages
+ */
+#[derive(Debug, Serialize)]
+pub struct DisadvantageReport<'a> {
+	pub disadvantages: &'a [Disadvantage],
+	pub total_ip: IpRange,
+}
+
+impl<'a> DisadvantageReport<'a> {
+	pub fn new(disadvantages: &'a [Disadvantage]) -> Self {
+		DisadvantageReport {
+			disadvantages,
+			total_ip: total_ip(disadvantages),
+		}
+	}
+}
+
+impl Display for DisadvantageReport<'_> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		let names: Vec<&str> = self.disadvantages.iter().map(Disadvantage::name).collect();
+		write!(f, "{}\nIP insgesamt: {}", names.join(", "), self.total_ip)
+	}
 }
 
 impl Loadable<Vec<Disadvantage>> for Disadvantage {
@@ -49,7 +130,7 @@ impl Loadable<Vec<Disadvantage>> for Disadvantage {
 						&Disadvantage::defaults(),
 					) {
 						Ok(_) => {
-							println!("Neue Nachteile wurden erzeugt");
+							eprintln!("Neue Nachteile wurden erzeugt");
 						}
 						Err(err) => {
 							eprintln!("{}", err)
@@ -67,17 +148,248 @@ impl Loadable<Vec<Disadvantage>> for Disadvantage {
 
 impl Disadvantage {
 	pub fn defaults() -> Vec<Disadvantage> {
-		return include!("default_disadvantage.rs");
+		include!("default_disadvantage.rs")
+	}
+
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub fn ip(&self) -> Option<IpRange> {
+		self.ip
+	}
+
+	pub fn excludes(&self, other: &str) -> bool {
+		self.excludes.iter().any(|name| name == other)
+	}
+
+	/// Whether the two disadvantages exclude each other directly or through a shared group
+	pub fn conflicts_with(&self, other: &Disadvantage) -> bool {
+		self.excludes(&other.name)
+			|| other.excludes(&self.name)
+			|| self.groups.iter().any(|group| other.groups.contains(group))
+	}
+
+	/// Checks the prerequisites and exclusions against everything known about the character
+	pub fn is_eligible(&self, character: &Character, all: &[Disadvantage]) -> bool {
+		let requirements_met = self.requires
+			.iter()
+			.all(|(attribute, minimum)| character.attributes.get(attribute).is_none_or(|value| value >= minimum));
+		let taken = character.disadvantages.contains(&self.name);
+		let excluded = character.disadvantages.iter().any(|name| {
+			match all.iter().find(|other| other.name == *name) {
+				Some(other) => self.conflicts_with(other),
+				None => self.excludes(name),
+			}
+		});
+		requirements_met && !taken && !excluded
 	}
 }
 
 impl Display for Disadvantage {
 	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-		write!(f, "{}\n{}", self.name, self.description)
+		write!(f, "{}\n{}", self.name, self.description)?;
+		if !self.fixes.is_empty() {
+			let fixes: Vec<String> = self.fixes.iter().map(|(attribute, value)| format!("{} = {}", attribute, value)).collect();
+			write!(f, "\nFest: {}", fixes.join(", "))?;
+		}
+		if !self.requires.is_empty() {
+			let requires: Vec<String> = self.requires.iter().map(|(attribute, value)| format!("{} >= {}", attribute, value)).collect();
+			write!(f, "\nVoraussetzung: {}", requires.join(", "))?;
+		}
+		if !self.excludes.is_empty() {
+			write!(f, "\nNicht kombinierbar mit: {}", self.excludes.join(", "))?;
+		}
+		Ok(())
 	}
 }
 
-pub fn get_random<T: Clone>(adv: &Vec<T>, rng: &mut impl Rng) -> T {
-	let uniform = Uniform::new_inclusive(0, adv.len() - 1).expect("Failed to create uniform distribution for advantages");
-	adv[rng.sample(uniform)].clone()
+/**
+Parses attributes like `UM=3 IN=2`, separated by spaces, `,` or `;`
+ */
+pub fn parse_attributes(input: &str) -> Result<BTreeMap<String, i64>, String> {
+	let normalized = input.split('=').map(str::trim).collect::<Vec<_>>().join("=");
+	normalized
+		.split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+		.filter(|part| !part.is_empty())
+		.map(|part| {
+			let (attribute, value) = part
+				.split_once('=')
+				.ok_or_else(|| format!("\"{}\" muss die Form Attribut=Wert haben", part))?;
+			let value = value
+				.parse::<i64>()
+				.map_err(|_| format!("\"{}\" ist keine Ganzzahl", value))?;
+			Ok((attribute.to_uppercase(), value))
+		})
+		.collect()
+}
+
+/**
+Picks a random disadvantage the character is eligible for, `None` if there is none
+ */
+pub fn get_random_for<'a>(disadvantages: &'a [Disadvantage], character: &Character, rng: &mut impl Rng) -> Option<&'a Disadvantage> {
+	let eligible: Vec<&Disadvantage> = disadvantages
+		.iter()
+		.filter(|disadvantage| disadvantage.is_eligible(character, disadvantages))
+		.collect();
+	get_random(&eligible, rng)
+}
+
+/// None for an empty list
+pub fn get_random<T: Clone>(adv: &[T], rng: &mut impl Rng) -> Option<T> {
+	let uniform = Uniform::new(0, adv.len()).ok()?;
+	Some(adv[rng.sample(uniform)].clone())
+}
+
+/**
+Draws up to `amount` distinct disadvantages which can all be combined with each other and the character
+ */
+pub fn draw(disadvantages: &[Disadvantage], character: &Character, amount: usize, rng: &mut impl Rng) -> Vec<Disadvantage> {
+	let mut character = character.clone();
+	let mut drawn = Vec::with_capacity(amount.min(disadvantages.len()));
+	while drawn.len() < amount {
+		match get_random_for(disadvantages, &character, rng) {
+			Some(disadvantage) => {
+				character.disadvantages.push(disadvantage.name.clone());
+				drawn.push(disadvantage.clone());
+			},
+			None => break,
+		}
+	}
+	drawn
+}
+
+/**
+Replaces the entry at `index` with a different disadvantage compatible with the rest of the set.
+Returns false and keeps the set unchanged if there is no alternative
+ */
+pub fn reroll(disadvantages: &[Disadvantage], character: &Character, drawn: &mut [Disadvantage], index: usize, rng: &mut impl Rng) -> bool {
+	let Some(replaced) = drawn.get(index) else {
+		return false;
+	};
+	let mut character = character.clone();
+	let kept: Vec<&Disadvantage> = drawn.iter()
+		.enumerate()
+		.filter(|(position, _)| *position != index)
+		.map(|(_, disadvantage)| disadvantage)
+		.collect();
+	character.disadvantages.extend(kept.iter().map(|disadvantage| disadvantage.name.clone()));
+	// Only the replaced entry itself is left out, whatever it excluded is possible again
+	let eligible: Vec<&Disadvantage> = disadvantages
+		.iter()
+		.filter(|disadvantage| disadvantage.name != replaced.name)
+		.filter(|disadvantage| disadvantage.is_eligible(&character, disadvantages))
+		.filter(|disadvantage| !kept.iter().any(|other| disadvantage.conflicts_with(other)))
+		.collect();
+	match get_random(&eligible, rng) {
+		Some(replacement) => {
+			drawn[index] = replacement.clone();
+			true
+		},
+		None => false,
+	}
+}
+
+/**
+Sum of the IP of all given disadvantages, disadvantages without IP count as 0
+ */
+pub fn total_ip(disadvantages: &[Disadvantage]) -> IpRange {
+	disadvantages
+		.iter()
+		.filter_map(|disadvantage| disadvantage.ip)
+		.fold(IpRange::default(), |total, ip| total + ip)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::disadvantage::{draw, get_random, parse_attributes, reroll, total_ip, Character, Disadvantage, IpRange};
+	use rand::rngs::StdRng;
+	use rand::SeedableRng;
+	use std::collections::BTreeMap;
+
+	fn find<'a>(disadvantages: &'a [Disadvantage], name: &str) -> &'a Disadvantage {
+		disadvantages.iter().find(|disadvantage| disadvantage.name() == name).unwrap()
+	}
+
+	#[test]
+	fn test_eligibility() {
+		let all = Disadvantage::defaults();
+		let asocial = find(&all, "Asozial");
+		let blind = find(&all, "Blind");
+
+		let unknown = Character::default();
+		assert!(asocial.is_eligible(&unknown, &all));
+
+		let character = Character {
+			attributes: BTreeMap::from([(String::from("UM"), 0)]),
+			disadvantages: vec![String::from("Kurzsichtig")],
+		};
+		assert!(!asocial.is_eligible(&character, &all));
+		assert!(!blind.is_eligible(&character, &all));
+		assert!(!find(&all, "Kurzsichtig").is_eligible(&character, &all));
+		assert!(find(&all, "Angst").is_eligible(&character, &all));
+	}
+
+	#[test]
+	fn test_parse_attributes() {
+		assert_eq!(
+			parse_attributes("um=3, IN = 2"),
+			Ok(BTreeMap::from([(String::from("IN"), 2), (String::from("UM"), 3)]))
+		);
+		assert!(parse_attributes("UM").is_err());
+		assert!(parse_attributes("UM=x").is_err());
+		assert_eq!(parse_attributes(""), Ok(BTreeMap::new()));
+	}
+
+	#[test]
+	fn test_draw_compatible() {
+		let all = Disadvantage::defaults();
+		let mut rng = StdRng::seed_from_u64(0);
+		for _ in 0..20 {
+			let mut drawn = draw(&all, &Character::default(), 10, &mut rng);
+			assert_eq!(drawn.len(), 10);
+			assert!(reroll(&all, &Character::default(), &mut drawn, 3, &mut rng));
+			for (index, first) in drawn.iter().enumerate() {
+				for second in &drawn[index + 1..] {
+					assert_ne!(first.name(), second.name());
+					assert!(!first.conflicts_with(second));
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn test_total_ip() {
+		let all = Disadvantage::defaults();
+		let chosen = vec![find(&all, "Angst").clone(), find(&all, "Feind").clone(), find(&all, "Blind").clone()];
+		assert_eq!(total_ip(&chosen), IpRange { min: 2, max: 10 });
+	}
+
+	#[test]
+	fn test_get_random_empty() {
+		let mut rng = StdRng::seed_from_u64(0);
+		assert_eq!(get_random::<Disadvantage>(&[], &mut rng), None);
+		assert_eq!(get_random(&[7], &mut rng), Some(7));
+	}
+
+	#[test]
+	fn test_draw_huge_amount() {
+		let all = Disadvantage::defaults();
+		let mut rng = StdRng::seed_from_u64(0);
+		let drawn = draw(&all, &Character::default(), usize::MAX, &mut rng);
+		assert!(!drawn.is_empty() && drawn.len() <= all.len());
+	}
+
+	#[test]
+	fn test_reroll_allows_excluded_by_replaced() {
+		let all = Disadvantage::defaults();
+		let chosen: Vec<Disadvantage> = ["Allergie", "Schwere Allergie", "Blind"].iter().map(|name| find(&all, name).clone()).collect();
+		let mut rng = StdRng::seed_from_u64(0);
+		let mut drawn = vec![chosen[0].clone(), chosen[2].clone()];
+		assert!(reroll(&chosen, &Character::default(), &mut drawn, 0, &mut rng));
+		assert_eq!(drawn[0].name(), "Schwere Allergie");
+		assert_eq!(drawn[1].name(), "Blind");
+		assert!(!reroll(&chosen, &Character::default(), &mut drawn, 1, &mut rng));
+		assert!(!reroll(&chosen, &Character::default(), &mut drawn, 2, &mut rng));
+	}
 }
@@ -0,0 +1,487 @@
+use crate::common::settings_path;
+use crate::edbgprintln;
+use crate::rng::{Generator, Seed, Seeds};
+use clap::builder::PossibleValue;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::fmt::{Display, Formatter};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One JSON object per line, so a roll only appends and only an undo rewrites the file
+const HISTORY_FILE: &str = "history.jsonl";
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/**
+What was rolled, the names are the same as the subcommands
+ */
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+	Roll,
+	Colored,
+	Crit,
+	Spell,
+	Disadvantage,
+	Advantage,
+	Table,
+	Decay,
+}
+
+impl Kind {
+	pub const ALL: [Kind; 8] = [
+		Kind::Roll,
+		Kind::Colored,
+		Kind::Crit,
+		Kind::Spell,
+		Kind::Disadvantage,
+		Kind::Advantage,
+		Kind::Table,
+		Kind::Decay,
+	];
+}
+
+impl ValueEnum for Kind {
+	fn value_variants<'a>() -> &'a [Self] {
+		&Kind::ALL
+	}
+
+	fn to_possible_value(&self) -> Option<PossibleValue> {
+		Some(match self {
+			Kind::Roll => PossibleValue::new("roll"),
+			Kind::Colored => PossibleValue::new("colored"),
+			Kind::Crit => PossibleValue::new("crit"),
+			Kind::Spell => PossibleValue::new("spell"),
+			Kind::Disadvantage => PossibleValue::new("disadvantage"),
+			Kind::Advantage => PossibleValue::new("advantage"),
+			Kind::Table => PossibleValue::new("table"),
+			Kind::Decay => PossibleValue::new("decay"),
+		}.help(self.to_string()))
+	}
+}
+
+impl Display for Kind {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		let name = match self {
+			Kind::Roll => "Normaler Würfel",
+			Kind::Colored => "Farbiger Würfel",
+			Kind::Crit => "Crit",
+			Kind::Spell => "Zauber",
+			Kind::Disadvantage => "Nachteil",
+			Kind::Advantage => "Vorteil",
+			Kind::Table => "Tabelle",
+			Kind::Decay => "Zerfallsreihe",
+		};
+		write!(f, "{}", name)
+	}
+}
+
+/**
+One roll in the history
+ */
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Entry {
+	/// Seconds since 1970-01-01 UTC
+	pub timestamp: u64,
+	#[serde(rename = "type")]
+	pub kind: Kind,
+	/// What was asked for, e.g. `5d6` or the name of a table
+	pub input: String,
+	/// Same structure as the JSON output of the subcommand
+	pub result: serde_json::Value,
+	/// Human readable result, shown again when the history is listed
+	pub text: String,
+	/// Replays the roll with `--seed`, missing for physical dice and the backends without seed
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub seed: Option<Seed>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub player: Option<String>,
+	/// The faces were entered from physical dice
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub physical: bool,
+	/// Start of the program run which made the roll in milliseconds since 1970-01-01 UTC, every subcommand is its own session
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub session: Option<u64>,
+	/// Commitment of the audited session, see `würfeln verify`
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub commitment: Option<String>,
+}
+
+impl Display for Entry {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{} | {}: {}", format_timestamp(self.timestamp), self.kind, self.input)?;
+		if let Some(player) = &self.player {
+			write!(f, " | {}", player)?;
+		}
+		if self.physical {
+			write!(f, " | echte Würfel")?;
+		}
+		write!(f, "\n{}", self.text)?;
+		if let Some(seed) = self.seed {
+			write!(f, "\nSeed: {}", seed)?;
+		}
+		Ok(())
+	}
+}
+
+/**
+Appends every roll to the history file in the data directory and keeps the rolls of this session
+ */
+pub struct History {
+	path: PathBuf,
+	/// Without it nothing is written, reading still works
+	enabled: bool,
+	pub player: Option<String>,
+	/// Set in the audit mode
+	pub commitment: Option<String>,
+	/// Sub-seed of the current roll
+	seed: Option<Seed>,
+	/// Whether a roll with the current sub-seed was recorded, only then the next roll gets a new one
+	used: Cell<bool>,
+	/// Start of this program run in milliseconds
+	session: u64,
+	/// Rolls of this program run, also kept if nothing is written
+	recorded: RefCell<Vec<Entry>>,
+}
+
+impl History {
+	pub fn new(player: Option<String>, enabled: bool) -> Self {
+		History::at(settings_path(HISTORY_FILE), player, enabled)
+	}
+
+	fn at(path: PathBuf, player: Option<String>, enabled: bool) -> Self {
+		History {
+			path,
+			enabled,
+			player: player.filter(|player| !player.trim().is_empty()),
+			commitment: None,
+			seed: None,
+			used: Cell::new(false),
+			session: SystemTime::now()
+				.duration_since(UNIX_EPOCH)
+				.map(|duration| duration.as_millis() as u64)
+				.unwrap_or_default(),
+			recorded: RefCell::new(vec![]),
+		}
+	}
+
+	/// Keeps the rolls only for the session, without a data directory
+	#[cfg(test)]
+	pub fn in_memory() -> Self {
+		History::at(PathBuf::new(), None, false)
+	}
+
+	/// Generator for the next roll, remembers its sub-seed for [`History::record`].
+	/// A sub-seed is only used up by recording a roll with it
+	pub fn next_rng(&mut self, seeds: &mut Seeds) -> Generator {
+		if self.used.take() {
+			seeds.advance();
+		}
+		let generator = seeds.next_rng();
+		self.seed = seeds.last();
+		generator
+	}
+
+	/// Records a roll made with the generator of the last [`History::next_rng`]
+	pub fn record(&self, kind: Kind, input: impl Into<String>, result: &impl Serialize, text: impl Display) {
+		self.write(kind, input.into(), result, text.to_string(), false);
+	}
+
+	/// Records faces entered from physical dice, they have no seed
+	pub fn record_entered(&self, kind: Kind, input: impl Into<String>, result: &impl Serialize, text: impl Display) {
+		self.write(kind, input.into(), result, text.to_string(), true);
+	}
+
+	/// Every roll since the program was started
+	pub fn session(&self) -> Vec<Entry> {
+		self.recorded.borrow().clone()
+	}
+
+	/// Removes the last roll of this session, also from the file. Not possible in the audit mode
+	pub fn undo(&self) -> Result<Entry, String> {
+		if self.commitment.is_some() {
+			return Err("Im Audit Modus können Würfe nicht rückgängig gemacht werden".to_string());
+		}
+		let entry = self.recorded.borrow().last().cloned().ok_or("In dieser Sitzung wurde noch nicht gewürfelt")?;
+		if self.enabled {
+			self.remove_last(&entry).map_err(|err| format!("{}: {}", self.path.display(), err))?;
+		}
+		self.recorded.borrow_mut().pop();
+		Ok(entry)
+	}
+
+	/// Removes the last line of the file if it is `entry`
+	fn remove_last(&self, entry: &Entry) -> Result<(), String> {
+		let content = fs::read_to_string(&self.path).map_err(|err| err.to_string())?;
+		let lines = content.trim_end_matches('\n');
+		let start = lines.rfind('\n').map(|index| index + 1).unwrap_or_default();
+		let last: Entry = serde_json::from_str(&lines[start..]).map_err(|err| err.to_string())?;
+		if (last.session, last.timestamp, &last.input) != (entry.session, entry.timestamp, &entry.input) {
+			return Err("Der letzte Wurf im Verlauf ist nicht aus dieser Sitzung".to_string());
+		}
+		fs::write(&self.path, &content[..start]).map_err(|err| err.to_string())
+	}
+
+	fn write(&self, kind: Kind, input: String, result: &impl Serialize, text: String, physical: bool) {
+		let result = match serde_json::to_value(result) {
+			Ok(result) => result,
+			Err(err) => {
+				edbgprintln!("Der Wurf konnte nicht in den Verlauf geschrieben werden: {}", err);
+				return;
+			},
+		};
+		let entry = Entry {
+			timestamp: now(),
+			kind,
+			input,
+			result,
+			text,
+			seed: if physical { None } else { self.seed },
+			player: self.player.clone(),
+			physical,
+			session: Some(self.session),
+			commitment: self.commitment.clone(),
+		};
+		self.recorded.borrow_mut().push(entry.clone());
+		if !physical {
+			self.used.set(true);
+		}
+		if !self.enabled {
+			return;
+		}
+		let written = serde_json::to_string(&entry)
+			.map_err(|err| err.to_string())
+			.and_then(|line| {
+				OpenOptions::new()
+					.create(true)
+					.append(true)
+					.open(&self.path)
+					.and_then(|mut file| writeln!(file, "{}", line))
+					.map_err(|err| err.to_string())
+			});
+		if let Err(err) = written {
+			edbgprintln!("Der Wurf konnte nicht in den Verlauf geschrieben werden: {}", err);
+		}
+	}
+
+	/// Every recorded roll, oldest first. Broken lines are reported and skipped
+	pub fn load(&self) -> Vec<Entry> {
+		let file = match File::open(&self.path) {
+			Ok(file) => file,
+			Err(_) => return vec![],
+		};
+		BufReader::new(file)
+			.lines()
+			.enumerate()
+			.filter_map(|(index, line)| {
+				let entry = line
+					.map_err(|err| err.to_string())
+					.and_then(|line| serde_json::from_str::<Entry>(&line).map_err(|err| err.to_string()));
+				match entry {
+					Ok(entry) => Some(entry),
+					Err(err) => {
+						edbgprintln!("{} Zeile {}: {}", self.path.display(), index + 1, err);
+						None
+					},
+				}
+			})
+			.collect()
+	}
+}
+
+/**
+Restricts the listed history, every field which is `None` matches everything
+ */
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct HistoryFilter {
+	pub kind: Option<Kind>,
+	/// Ignores the case
+	pub player: Option<String>,
+	/// First day, as timestamp of its start
+	pub since: Option<u64>,
+	/// Last day, as timestamp of its start
+	pub until: Option<u64>,
+	pub session: Option<u64>,
+}
+
+impl HistoryFilter {
+	pub fn matches(&self, entry: &Entry) -> bool {
+		self.kind.is_none_or(|kind| kind == entry.kind)
+			&& self.player.as_ref().is_none_or(|player| {
+				entry.player.as_ref().is_some_and(|other| other.to_lowercase() == player.to_lowercase())
+			})
+			&& self.since.is_none_or(|since| entry.timestamp >= since)
+			&& self.until.is_none_or(|until| entry.timestamp < until.saturating_add(SECONDS_PER_DAY))
+			&& self.session.is_none_or(|session| entry.session == Some(session))
+	}
+
+	/// Matching entries with their number in the whole history, starting at 1
+	pub fn apply<'a>(&self, entries: &'a [Entry]) -> Vec<(usize, &'a Entry)> {
+		entries.iter()
+			.enumerate()
+			.filter(|(_, entry)| self.matches(entry))
+			.map(|(index, entry)| (index + 1, entry))
+			.collect()
+	}
+}
+
+impl Display for HistoryFilter {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		let mut parts = vec![];
+		if let Some(kind) = self.kind {
+			parts.push(format!("Typ {}", kind));
+		}
+		if let Some(player) = &self.player {
+			parts.push(format!("Spieler {}", player));
+		}
+		if let Some(since) = self.since {
+			parts.push(format!("ab {}", format_date(since)));
+		}
+		if let Some(until) = self.until {
+			parts.push(format!("bis {}", format_date(until)));
+		}
+		if let Some(session) = self.session {
+			parts.push(format!("Sitzung vom {}", format_timestamp(session / 1000)));
+		}
+		if parts.is_empty() {
+			write!(f, "keiner")
+		} else {
+			write!(f, "{}", parts.join(", "))
+		}
+	}
+}
+
+fn now() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|duration| duration.as_secs())
+		.unwrap_or_default()
+}
+
+/// Days since 1970-01-01 of a date in the gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+	let year = if month <= 2 { year - 1 } else { year };
+	let era = if year >= 0 { year } else { year - 399 } / 400;
+	let year_of_era = year - era * 400;
+	let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+	let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+	era * 146097 + day_of_era - 719468
+}
+
+/// Inverse of [`days_from_civil`], returns year, month and day
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+	let days = days + 719468;
+	let era = if days >= 0 { days } else { days - 146096 } / 146097;
+	let day_of_era = days - era * 146097;
+	let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let shifted_month = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+	let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+	(year_of_era + era * 400 + (month <= 2) as i64, month, day)
+}
+
+fn format_date(timestamp: u64) -> String {
+	let (year, month, day) = civil_from_days((timestamp / SECONDS_PER_DAY) as i64);
+	format!("{:02}.{:02}.{}", day, month, year)
+}
+
+/**
+Date and time in UTC, the history does not know the time zone of the roll
+ */
+pub fn format_timestamp(timestamp: u64) -> String {
+	let seconds = timestamp % SECONDS_PER_DAY;
+	format!(
+		"{} {:02}:{:02}:{:02} UTC",
+		format_date(timestamp),
+		seconds / 3600,
+		seconds / 60 % 60,
+		seconds % 60
+	)
+}
+
+/**
+Parses `19.10.2026` or `2026-10-19` into the timestamp of the start of that day in UTC
+ */
+pub fn parse_date(input: &str) -> Result<u64, String> {
+	let input = input.trim();
+	let error = || format!("Ungültiges Datum: {}, erwartet z.B. 19.10.2026 oder 2026-10-19", input);
+	let parts: Vec<i64> = input
+		.split(['.', '-'])
+		.map(|part| part.trim().parse::<i64>())
+		.collect::<Result<_, _>>()
+		.map_err(|_| error())?;
+	let (year, month, day) = match parts.as_slice() {
+		[day, month, year] if input.contains('.') => (*year, *month, *day),
+		[year, month, day] if input.contains('-') => (*year, *month, *day),
+		_ => return Err(error()),
+	};
+	// Huge years would overflow the calculation
+	if !(1970..=9999).contains(&year) {
+		return Err(error());
+	}
+	let days = days_from_civil(year, month, day);
+	if days < 0 || civil_from_days(days) != (year, month, day) {
+		return Err(error());
+	}
+	Ok(days as u64 * SECONDS_PER_DAY)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::history::{format_timestamp, parse_date, Entry, HistoryFilter, Kind};
+
+	#[test]
+	fn test_dates() {
+		assert_eq!(parse_date("01.01.1970"), Ok(0));
+		assert_eq!(parse_date("2026-10-19"), parse_date("19.10.2026"));
+		assert_eq!(parse_date("29.02.2024").map(format_timestamp), Ok("29.02.2024 00:00:00 UTC".to_string()));
+		assert_eq!(format_timestamp(1_792_418_645), "19.10.2026 14:04:05 UTC");
+		assert!(parse_date("29.02.2026").is_err());
+		assert!(parse_date("2026-13-01").is_err());
+		assert!(parse_date("gestern").is_err());
+		assert!(parse_date("31.12.1969").is_err());
+		assert!(parse_date("01.01.10000").is_err());
+		assert!(parse_date("9223372036854775807-01-01").is_err());
+		assert!(parse_date("31.12.9999").is_ok());
+	}
+
+	#[test]
+	fn test_filter() {
+		let entry = |kind, player: Option<&str>, timestamp| Entry {
+			timestamp,
+			kind,
+			input: String::new(),
+			result: serde_json::Value::Null,
+			text: String::new(),
+			seed: None,
+			player: player.map(str::to_string),
+			physical: false,
+			session: None,
+			commitment: None,
+		};
+		let day = parse_date("19.10.2026").unwrap();
+		let entries = vec![
+			entry(Kind::Roll, Some("Anna"), day - 1),
+			entry(Kind::Roll, None, day + 10),
+			entry(Kind::Crit, Some("anna"), day + 86399),
+			entry(Kind::Roll, Some("Anna"), day + 86400),
+		];
+		let filter = HistoryFilter {
+			player: Some("ANNA".to_string()),
+			since: Some(day),
+			until: Some(day),
+			..Default::default()
+		};
+		let numbers: Vec<usize> = filter.apply(&entries).into_iter().map(|(number, _)| number).collect();
+		assert_eq!(numbers, vec![3]);
+		let roll = HistoryFilter { kind: Some(Kind::Roll), ..Default::default() };
+		assert_eq!(roll.apply(&entries).len(), 3);
+		let open_end = HistoryFilter { until: Some(u64::MAX), ..Default::default() };
+		assert_eq!(open_end.apply(&entries).len(), 4);
+	}
+}
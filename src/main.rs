@@ -3,29 +3,50 @@ mod color;
 mod dice;
 mod spell;
 mod disadvantage;
+mod advantage;
 mod decay_series;
 mod common;
+mod bestiary;
+mod commands;
+mod table;
+mod output;
+mod rng;
+mod history;
+mod statistics;
+mod audit;
+mod repl;
+mod tui;
 
-#[cfg(target_arch = "x86_64")]
-use std::arch::x86_64::_rdseed64_step;
+use ansi_term::Style;
 use color::get_color;
 use crate::preferences::Settings;
+use clap::error::ErrorKind;
 use clap::{Arg, Command};
-use dice::colored_dice::{ColoredDice, ColoredDices};
-use common::{Loadable, Rollable};
+use dice::colored_dice::{ColoredDice, ColoredDices, ColoredReport};
+use common::{settings_path, Loadable, Rollable};
+use bestiary::BestiaryEntry;
 use dice::crit_dice::CritDices;
 use dialoguer::console::Term;
-use dialoguer::{Input, MultiSelect, Select};
-use disadvantage::Disadvantage;
+use dialoguer::{FuzzySelect, Input, MultiSelect, Select};
+use disadvantage::{Character, Disadvantage, DisadvantageReport};
+use advantage::Advantage;
 use dice::normal_dice::Dices;
 use std::io;
 use std::io::Write;
+use std::process::exit;
+#[cfg(debug_assertions)]
 use std::time::SystemTime;
-use spell::Spells;
-use decay_series::Operation;
+use spell::{DrawnSpell, SpellFilter, Spells};
+use table::Table;
+use output::Format;
+use rng::{Backend, Seed, Seeds};
+use history::{Entry, History, HistoryFilter, Kind};
+use statistics::Report;
+use repl::Repl;
+use audit::Rules;
+use decay_series::{Classification, Graph, Operation, Session, Step};
 use decay_series::State;
-use rand::rngs::StdRng;
-use rand::{Rng, SeedableRng};
+use rand::Rng;
 
 /**
  * Prints basic information's about the usage of the program
@@ -56,13 +77,16 @@ fn print_startup_information(allowed_coloured_dices: &ColoredDices, allowed_dice
 	}
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle_input(
 	input: &str,
 	old_report_style: bool,
+	physical: bool,
 	allowed_colored_dices: &ColoredDices,
 	allowed_dice_sites: &Dices,
 	error_message: &str,
 	no_summary: bool,
+	history: &History,
 	rng: &mut impl Rng
 ) -> bool {
 	if input == "exit" || input == "e" {
@@ -76,9 +100,20 @@ fn handle_input(
 			dbgprintln!("Es muss eine Ganzzahl sein, wie oben beschrieben");
 		} else if let Ok(sides) = parsed {
 			if allowed_dice_sites.dices.contains(&sides) {
+				if physical {
+					let prompt = format!("Augenzahlen der W{}", sides);
+					if let Some(res) = ask_for_faces(&prompt, |faces| dice::normal_dice::from_faces(sides, faces)) {
+						res.print_results(old_report_style, no_summary);
+						let report = res.report();
+						history.record_entered(Kind::Roll, format!("{}d{}", report.amount, sides), &[&report], &report);
+					}
+					return false;
+				}
 				let amount = ask_for_amount(error_message, "Anzahl");
 				let res = dice::normal_dice::roll(amount, sides, old_report_style, rng);
 				res.print_results(old_report_style, no_summary);
+				let report = res.report();
+				history.record(Kind::Roll, format!("{}d{}", amount, sides), &[&report], &report);
 			} else {
 				dbgprintln!("Die ist nicht erlaubt...")
 			}
@@ -98,76 +133,728 @@ fn ask_for_amount(error_message: &str, prompt: &str) -> usize {
 		.interact_text();
 	input.unwrap_or_else(|_e| "0".to_string())
 		.parse()
-		.unwrap_or_else(|_e| 0)
+		.unwrap_or(0)
 }
 
-fn validator(val: &String) -> Result<(), &'static str> {
-	let new_val = val.trim();
-	if new_val.is_empty() {
-		return Err("Bitte etwas eingeben");
-	}
+/// Like [`ask_for_amount`], but rejects amounts above `max`
+fn ask_for_amount_up_to(error_message: &str, prompt: &str, max: usize) -> usize {
+	let input = Input::new()
+		.with_prompt(format!("{} (höchstens {})", prompt, max))
+		.validate_with(|input: &String| -> Result<(), String> {
+			match input.parse::<usize>() {
+				Ok(amount) if amount <= max => Ok(()),
+				Ok(_) => Err(format!("Höchstens {}", max)),
+				Err(_) => Err(error_message.to_string()),
+			}
+		})
+		.interact_text();
+	input.unwrap_or_else(|_e| "0".to_string())
+		.parse()
+		.unwrap_or(0)
+}
 
-	i64::from_str_radix(new_val, 10)
-		.map(|_| ())
-		.map_err(|_| "Bitte eine positive oder negative Ganzzahl eingeben")
+/**
+Asks for the faces of physical dice until `check` accepts them
+ */
+fn ask_for_faces<T>(prompt: &str, check: impl Fn(Vec<u8>) -> Result<T, String>) -> Option<T> {
+	let input = Input::<String>::new()
+		.with_prompt(prompt)
+		.validate_with(|input: &String| -> Result<(), String> {
+			dice::parse_faces(input).and_then(&check).map(|_| ())
+		})
+		.interact_text()
+		.ok()?;
+	dice::parse_faces(&input).and_then(check).ok()
 }
 
-fn decay_series(stdout: &Term, operation: &Vec<Operation>) {
-	let protons_input = Input::new()
-		.with_prompt("Protonen")
-		.validate_with(validator)
-		.interact_text();
-	let neutrons_input = Input::new()
-		.with_prompt("Neutronen")
-		.validate_with(validator)
-		.interact_text();
-	let electrons_input = Input::new()
-		.with_prompt("Elektronen")
-		.validate_with(validator)
+fn ask_for_state(stdout: &Term) -> State {
+	let input = Input::<String>::new()
+		.with_prompt("Zustand (Elektronen;Protonen;Neutronen oder e=.. p=.. n=..)")
+		.validate_with(|input: &String| -> Result<(), String> {
+			input.parse::<State>()
+				.map(|_| ())
+				.map_err(|err| err.to_string())
+		})
 		.interact_text();
-	if let Err(e) = stdout.clear_last_lines(3) {
+	if let Err(e) = stdout.clear_last_lines(1) {
 		edbgprintln!("Terminal Fehler: {}", e);
 	}
 
-	let protons = protons_input
-		.map(|inp| i64::from_str_radix(inp.trim(), 10).unwrap_or_else(|_| 0))
-		.unwrap_or_else(|_| 0);
-
-	let neutrons = neutrons_input
-		.map(|inp| i64::from_str_radix(inp.trim(), 10).unwrap_or_else(|_| 0))
-		.unwrap_or_else(|_| 0);
-
-	let electrons = electrons_input
-		.map(|inp| i64::from_str_radix(inp.trim(), 10).unwrap_or_else(|_| 0))
-		.unwrap_or_else(|_| 0);
+	input.map_err(|err| err.to_string())
+		.and_then(|input| input.parse::<State>().map_err(|err| err.to_string()))
+		.unwrap_or_else(|err| {
+			edbgprintln!("{}", err);
+			State::default()
+		})
+}
 
-	let mut state = State::from((electrons, protons, neutrons));
-	let mut options: Vec<String> = operation.iter().map(|x| x.display.clone()).collect();
-	options.push(String::from("Aufhören"));
+fn decay_series(
+	stdout: &Term,
+	session: Session,
+	operation: &[Operation],
+	classifications: &[Classification],
+	bestiary: &[BestiaryEntry],
+	history: &History,
+	rng: &mut impl Rng
+) {
+	let mut session = session;
 	#[cfg(debug_assertions)]
 	dbgprintln!("{:?}", operation);
 	loop {
+		let state = session.state;
+		let mut options: Vec<String> = operation.iter()
+			.map(|op| match op.check(&state, classifications) {
+				Ok(_) => op.label(),
+				Err(_) => Style::new().dimmed().paint(op.label()).to_string(),
+			})
+			.collect();
+		options.push(String::from("Speichern"));
+		options.push(String::from("Aufhören"));
 		let selection = Select::new()
 			.with_prompt("Operation")
 			.items(&options)
 			.default(0)
 			.interact();
-		let i = match selection {
-			Ok(i) if i < operation.len() => i,
+		let op = match selection {
+			Ok(i) if i < operation.len() => &operation[i],
+			Ok(i) if i == operation.len() => {
+				save_decay_series(&mut session);
+				continue;
+			},
 			_ => break,
 		};
 
-		let new = operation[i].apply(state);
-		if new.protons < 0 || new.electrons < 0 || new.neutrons < 0 {
-			dbgprintln!("Nicht möglich!");
-		} else {
-			state = new;
-			dbgprintln!("{}", state);
+		if let Err(reason) = op.check(&state, classifications) {
+			dbgprintln!("Nicht möglich! {}", reason);
+			continue;
+		}
+		if let Some(cost) = &op.cost {
+			session.spent.add(cost);
+		}
+		let success = op.succeeds(rng);
+		let step = Step {
+			operation: op.display.clone(),
+			state: if success { op.apply(state) } else { state },
+			success,
+		};
+		let description = step.state.get_description(classifications);
+		let text = match success {
+			true => format!("{}\n{}", description, step.state),
+			false => "fehlgeschlagen".to_string(),
+		};
+		let result = serde_json::json!({ "start": state, "steps": [&step], "state": step.state, "description": description });
+		history.record(Kind::Decay, &op.display, &result, text);
+		session.record(step);
+		if !success {
+			dbgprintln!("{} ist fehlgeschlagen!", op);
+			continue;
+		}
+
+		let state = session.state;
+		dbgprintln!("{}\n{}", state.get_description(classifications), state);
+		if let Some(entry) = bestiary::lookup(bestiary, classifications, &state) {
+			dbgprintln!("\n{}", entry);
 		}
 	}
 	if let Err(e) = stdout.clear_last_lines(3) {
 		edbgprintln!("Terminal Fehler: {}", e);
 	}
+	if !session.spent.is_empty() {
+		dbgprintln!("Kosten insgesamt: {}", session.spent);
+	}
+}
+
+fn save_decay_series(session: &mut Session) {
+	let name = Input::<String>::new()
+		.with_prompt("Name der Zerfallsreihe")
+		.with_initial_text(session.name.clone())
+		.validate_with(|input: &String| -> Result<(), &str> {
+			if input.trim().is_empty() {
+				Err("Bitte etwas eingeben")
+			} else {
+				Ok(())
+			}
+		})
+		.interact_text();
+	match name {
+		Ok(name) => {
+			session.name = name.trim().to_string();
+			match session.save() {
+				Ok(path) => dbgprintln!("Gespeichert unter {}", path.display()),
+				Err(err) => edbgprintln!("Fehler beim Speichern: {}", err),
+			}
+		},
+		Err(err) => edbgprintln!("{}", err),
+	}
+}
+
+fn resume_decay_series(
+	stdout: &Term,
+	operation: &[Operation],
+	classifications: &[Classification],
+	bestiary: &[BestiaryEntry],
+	history: &History,
+	rng: &mut impl Rng
+) {
+	let saved = Session::list();
+	if saved.is_empty() {
+		dbgprintln!("Es gibt noch keine gespeicherten Zerfallsreihen");
+		return;
+	}
+	let selection = Select::new()
+		.with_prompt("Zerfallsreihe")
+		.items(&saved)
+		.default(0)
+		.interact_opt()
+		.unwrap_or(None)
+		.and_then(|index| saved.get(index));
+	let session = match selection.map(|name| Session::load_named(name)) {
+		Some(Ok(session)) => session,
+		Some(Err(err)) => {
+			edbgprintln!("Fehler beim Laden: {}", err);
+			return;
+		},
+		None => return,
+	};
+
+	dbgprintln!("Start: {}\n{}\n", session.start.get_description(classifications), session.start);
+	for (index, step) in session.history.iter().enumerate() {
+		if step.success {
+			dbgprintln!("{}. {}: {}", index + 1, step.operation, step.state.get_description(classifications));
+		} else {
+			dbgprintln!("{}. {}: fehlgeschlagen", index + 1, step.operation);
+		}
+	}
+	dbgprintln!("\nAktuell: {}\n{}", session.state.get_description(classifications), session.state);
+	decay_series(stdout, session, operation, classifications, bestiary, history, rng);
+}
+
+fn export_decay_series(stdout: &Term, operation: &[Operation], classifications: &[Classification], error_message: &str) {
+	let start = ask_for_state(stdout);
+	let depth = ask_for_amount_up_to(error_message, "Maximale Anzahl an Operationen", decay_series::MAX_DEPTH);
+	let name: String = Input::new()
+		.with_prompt("Dateiname")
+		.default(String::from("zerfallsreihe"))
+		.validate_with(|input: &String| -> Result<(), &str> {
+			match input.trim() {
+				"" => Err("Der Dateiname darf nicht leer sein"),
+				name if name.contains(['/', '\\']) => Err("Der Dateiname darf keinen Ordner enthalten"),
+				_ => Ok(()),
+			}
+		})
+		.interact_text()
+		.unwrap_or_else(|_| String::from("zerfallsreihe"));
+
+	let graph = Graph::explore(start, operation, classifications, depth);
+	let base = settings_path(name.trim());
+	let (dot, json) = Graph::files(&base);
+	match graph.export(&base) {
+		Ok(_) => dbgprintln!(
+			"{} Zustände und {} Übergänge nach {} und {} geschrieben",
+			graph.nodes.len(),
+			graph.edges.len(),
+			dot.display(),
+			json.display()
+		),
+		Err(err) => edbgprintln!("Fehler beim Exportieren: {}", err),
+	}
+}
+
+fn random_decay_series(
+	stdout: &Term,
+	operation: &[Operation],
+	classifications: &[Classification],
+	bestiary: &[BestiaryEntry],
+	error_message: &str,
+	history: &History,
+	rng: &mut impl Rng
+) {
+	let start_options = ["Zufälliger Startzustand", "Eigener Startzustand"];
+	let start = match Select::new()
+		.with_prompt("Start")
+		.items(start_options)
+		.default(0)
+		.interact_opt()
+		.unwrap_or(None) {
+		Some(0) => {
+			let max = ask_for_amount(error_message, "Maximale Teilchenzahl");
+			decay_series::random_state(max as i64, rng)
+		},
+		Some(_) => ask_for_state(stdout),
+		None => return,
+	};
+	let steps = ask_for_amount_up_to(error_message, "Anzahl Operationen", decay_series::MAX_STEPS);
+
+	dbgprintln!("Start: {}\n{}\n", start.get_description(classifications), start);
+	let walk = decay_series::random_walk(start, operation, classifications, steps, rng);
+	for (index, step) in walk.iter().enumerate() {
+		if step.success {
+			dbgprintln!("{}. {}: {}", index + 1, step.operation, step.state.get_description(classifications));
+		} else {
+			dbgprintln!("{}. {}: fehlgeschlagen", index + 1, step.operation);
+		}
+	}
+	if walk.len() < steps {
+		dbgprintln!("Keine Operation mehr möglich");
+	}
+
+	let end = walk.last().map(|step| step.state).unwrap_or(start);
+	dbgprintln!("\nErgebnis: {}\n{}", end.get_description(classifications), end);
+	let description = end.get_description(classifications);
+	history.record(
+		Kind::Decay,
+		format!("{} zufällige Operationen", steps),
+		&serde_json::json!({ "start": start, "steps": walk, "state": end, "description": description }),
+		format!("Ergebnis: {}\n{}", description, end),
+	);
+	if let Some(entry) = bestiary::lookup(bestiary, classifications, &end) {
+		dbgprintln!("\n{}", entry);
+	}
+}
+
+fn browse_bestiary(entries: &[BestiaryEntry]) {
+	let categories = bestiary::categories(entries);
+	if categories.is_empty() {
+		dbgprintln!("Das Bestiarium ist leer");
+		return;
+	}
+
+	let category = Select::new()
+		.with_prompt("Kategorie")
+		.items(&categories)
+		.default(0)
+		.interact_opt()
+		.unwrap_or(None)
+		.and_then(|index| categories.get(index));
+	let category = match category {
+		Some(category) => *category,
+		None => return,
+	};
+
+	let mut in_category: Vec<&BestiaryEntry> = entries.iter()
+		.filter(|entry| entry.category == category)
+		.collect();
+	in_category.sort_by_key(|entry| entry.number);
+	let items: Vec<String> = in_category.iter()
+		.map(|entry| format!("Nr. {}: {}", entry.number, entry.name))
+		.collect();
+
+	loop {
+		let selection = Select::new()
+			.with_prompt(category)
+			.items(&items)
+			.default(0)
+			.interact_opt()
+			.unwrap_or(None);
+		match selection {
+			Some(index) => dbgprintln!("{}\n", in_category[index]),
+			None => break,
+		}
+	}
+}
+
+fn roll_spells(spells: &[Spells], error_message: &str, history: &History, rng: &mut impl Rng) {
+	let modes = [
+		"Ein Zauber aus einer Kategorie",
+		"Mehrere Zauber aus einer Kategorie",
+		"Mehrere Zauber aus ausgewählten Kategorien",
+		"Mehrere Zauber aus allen Kategorien",
+	];
+	let mode = match Select::new()
+		.with_prompt("Zauber")
+		.items(modes)
+		.default(0)
+		.interact_opt()
+		.unwrap_or(None) {
+		Some(mode) => mode,
+		None => return,
+	};
+
+	let search_string = "Kampfzauber";
+	let items: Vec<&str> = spells.iter().map(|x| &*x.name).collect();
+	let default = items.iter().position(|x| *x == search_string).unwrap_or(0);
+
+	let categories: Vec<&Spells> = match mode {
+		0 | 1 => {
+			let selection = Select::new()
+				.items(&items)
+				.default(default)
+				.interact_opt()
+				.unwrap_or(None);
+			match selection {
+				Some(index) if mode == 0 => {
+					let Some(spell) = spells[index].roll(rng) else {
+						dbgprintln!("{} enthält keinen Zauber mit einer Gewichtung über 0", spells[index].name);
+						return;
+					};
+					dbgprintln!("{}", spell);
+					let drawn = [DrawnSpell::new(&spells[index].name, &spell)];
+					history.record(Kind::Spell, format!("{} x1", spells[index].name), &drawn, format!("{}: {}", spells[index].name, spell));
+					return;
+				},
+				Some(index) => vec![&spells[index]],
+				None => return,
+			}
+		},
+		2 => {
+			let defaults: Vec<bool> = (0..items.len()).map(|index| index == default).collect();
+			MultiSelect::new()
+				.items(&items)
+				.defaults(&defaults)
+				.with_prompt("Wähle die Kategorien (Mit der Leertaste auswählen und Enter bestätigen)")
+				.interact_opt()
+				.unwrap_or(None)
+				.unwrap_or_default()
+				.into_iter()
+				.filter_map(|index| spells.get(index))
+				.collect()
+		},
+		_ => spells.iter().collect(),
+	};
+	if categories.is_empty() {
+		dbgprintln!("Nichts gewählt.");
+		return;
+	}
+
+	let amount = ask_for_amount(error_message, "Anzahl Zauber");
+	let drawn = spell::draw(&categories, amount, rng);
+	if drawn.len() < amount {
+		dbgprintln!("Es gibt nur {} verschiedene Zauber mit einer Gewichtung über 0", drawn.len());
+	}
+	let text: Vec<String> = drawn.iter().map(|(category, spell)| format!("{}: {}", category, spell)).collect();
+	for line in &text {
+		dbgprintln!("{}", line);
+	}
+	let names: Vec<&str> = categories.iter().map(|category| category.name.as_str()).collect();
+	let report: Vec<DrawnSpell> = drawn.iter().map(|(category, spell)| DrawnSpell::new(category, spell)).collect();
+	history.record(Kind::Spell, format!("{} x{}", names.join(", "), amount), &report, text.join("\n"));
+}
+
+fn browse_spells(spells: &[Spells], error_message: &str) {
+	let mut filter = SpellFilter::default();
+	let actions = ["Suchen", "Filter setzen", "Filter zurücksetzen", "Zurück"];
+	loop {
+		dbgprintln!("Filter: {}", filter);
+		let action = Select::new()
+			.with_prompt("Zauberbuch")
+			.items(actions)
+			.default(0)
+			.interact_opt()
+			.unwrap_or(None);
+		match action {
+			Some(0) => {
+				let found = filter.apply(spells);
+				if found.is_empty() {
+					dbgprintln!("Kein Zauber passt zum Filter");
+					continue;
+				}
+				let items: Vec<String> = found.iter()
+					.map(|(category, spell)| format!("{} ({})", spell.name, category))
+					.collect();
+				let selection = FuzzySelect::new()
+					.with_prompt("Zauber suchen")
+					.items(&items)
+					.default(0)
+					.interact_opt()
+					.unwrap_or(None);
+				if let Some(index) = selection {
+					let (category, spell) = found[index];
+					dbgprintln!("{}\nKategorie: {}\nGewichtung: {}\n", spell, category, spell.weight);
+				}
+			},
+			Some(1) => filter = ask_for_spell_filter(spells, error_message),
+			Some(2) => filter = SpellFilter::default(),
+			_ => break,
+		}
+	}
+}
+
+/// Asks for a numeric filter of the spells, 0 means no restriction
+fn ask_for_limit(error_message: &str, prompt: &str) -> Option<u32> {
+	let limit = |input: &str| -> Result<u32, String> {
+		let value = input.parse::<u64>().map_err(|_| error_message.to_string())?;
+		u32::try_from(value).map_err(|_| format!("Höchstens {}", u32::MAX))
+	};
+	let input = Input::<String>::new()
+		.with_prompt(prompt)
+		.validate_with(|input: &String| -> Result<(), String> {
+			limit(input).map(|_| ())
+		})
+		.interact_text()
+		.ok()?;
+	limit(&input).ok().filter(|&value| value != 0)
+}
+
+fn ask_for_spell_filter(spells: &[Spells], error_message: &str) -> SpellFilter {
+	let items: Vec<&str> = spells.iter().map(|x| &*x.name).collect();
+	let categories = MultiSelect::new()
+		.items(&items)
+		.with_prompt("Kategorien, keine Auswahl für alle (Mit der Leertaste auswählen und Enter bestätigen)")
+		.interact_opt()
+		.unwrap_or(None)
+		.unwrap_or_default()
+		.into_iter()
+		.filter_map(|index| items.get(index).map(|name| name.to_string()))
+		.collect();
+
+	let max_mana = ask_for_limit(error_message, "Maximales Mana (0 für beliebig)");
+	let level = ask_for_limit(error_message, "Level des Charakters (0 für beliebig)");
+	let range: String = Input::new()
+		.with_prompt("Reichweite enthält (leer für beliebig)")
+		.allow_empty(true)
+		.interact_text()
+		.unwrap_or_default();
+
+	SpellFilter {
+		categories,
+		max_mana,
+		level,
+		range: Some(range.trim().to_string()).filter(|range| !range.is_empty()),
+	}
+}
+
+fn ask_for_character(disadvantages: &[Disadvantage]) -> Option<Character> {
+	let attributes = Input::<String>::new()
+		.with_prompt("Attribute (z.B. UM=3 IN=2, leer wenn unbekannt)")
+		.allow_empty(true)
+		.validate_with(|input: &String| -> Result<(), String> {
+			disadvantage::parse_attributes(input).map(|_| ())
+		})
+		.interact_text()
+		.ok()
+		.and_then(|input| disadvantage::parse_attributes(&input).ok())?;
+
+	let names: Vec<&str> = disadvantages.iter().map(|disadvantage| disadvantage.name()).collect();
+	let taken = MultiSelect::new()
+		.items(&names)
+		.with_prompt("Vorhandene Nachteile (Mit der Leertaste auswählen und Enter bestätigen)")
+		.interact_opt()
+		.unwrap_or(None)?
+		.into_iter()
+		.filter_map(|index| names.get(index).map(|name| name.to_string()))
+		.collect();
+
+	Some(Character {
+		attributes,
+		disadvantages: taken,
+	})
+}
+
+fn roll_disadvantage(disadvantages: &[Disadvantage], error_message: &str, history: &History, rng: &mut impl Rng) {
+	let amounts = ["Ein Nachteil", "Mehrere Nachteile"];
+	let multiple = match Select::new()
+		.items(amounts)
+		.default(0)
+		.interact_opt()
+		.unwrap_or(None) {
+		Some(selection) => selection == 1,
+		None => return,
+	};
+
+	let modes = ["Beliebiger Nachteil", "Für einen Charakter"];
+	let character = match Select::new()
+		.items(modes)
+		.default(0)
+		.interact_opt()
+		.unwrap_or(None) {
+		Some(0) => Character::default(),
+		Some(_) => match ask_for_character(disadvantages) {
+			Some(character) => character,
+			None => return,
+		},
+		None => return,
+	};
+
+	if multiple {
+		roll_disadvantages(disadvantages, &character, error_message, history, rng);
+		return;
+	}
+
+	match disadvantage::get_random_for(disadvantages, &character, rng) {
+		Some(rando) => {
+			dbgprintln!("{}", rando);
+			if let Some(ip) = rando.ip() {
+				dbgprintln!("Erhaltene IP: {}", ip);
+			}
+			let report = DisadvantageReport::new(std::slice::from_ref(rando));
+			history.record(Kind::Disadvantage, "1", &report, &report);
+		},
+		None => dbgprintln!("Der Charakter kann keinen weiteren Nachteil wählen"),
+	}
+}
+
+fn roll_disadvantages(disadvantages: &[Disadvantage], character: &Character, error_message: &str, history: &History, rng: &mut impl Rng) {
+	let amount = ask_for_amount(error_message, "Anzahl Nachteile");
+	let mut drawn = disadvantage::draw(disadvantages, character, amount, rng);
+	if drawn.len() < amount {
+		dbgprintln!("Es gibt nur {} kombinierbare Nachteile", drawn.len());
+	}
+	if drawn.is_empty() {
+		return;
+	}
+
+	loop {
+		for (index, rando) in drawn.iter().enumerate() {
+			dbgprintln!("{}. {}", index + 1, rando);
+			if let Some(ip) = rando.ip() {
+				dbgprintln!("Erhaltene IP: {}", ip);
+			}
+		}
+		dbgprintln!("IP insgesamt: {}", disadvantage::total_ip(&drawn));
+
+		let mut items: Vec<String> = drawn.iter()
+			.map(|rando| format!("{} neu würfeln", rando.name()))
+			.collect();
+		items.push("Fertig".to_string());
+		let index = match Select::new()
+			.items(&items)
+			.default(items.len() - 1)
+			.interact_opt()
+			.unwrap_or(None) {
+			Some(index) if index < drawn.len() => index,
+			_ => {
+				// Only the final set is recorded, rerolls replace entries of it
+				let report = DisadvantageReport::new(&drawn);
+				history.record(Kind::Disadvantage, amount.to_string(), &report, &report);
+				return;
+			},
+		};
+		if !disadvantage::reroll(disadvantages, character, &mut drawn, index, rng) {
+			dbgprintln!("Es gibt keinen anderen passenden Nachteil");
+		}
+	}
+}
+
+fn roll_table(tables: &[Table], label: &str, history: &History, rng: &mut impl Rng) {
+	let Some(table) = tables.iter().find(|table| table.label() == label) else {
+		return;
+	};
+	match table.roll(tables, rng) {
+		Ok(results) => {
+			let text: Vec<String> = results.iter().map(ToString::to_string).collect();
+			for line in &text {
+				dbgprintln!("{}", line);
+			}
+			history.record(Kind::Table, &table.name, &results, text.join("\n"));
+		},
+		Err(err) => edbgprintln!("{}", err),
+	}
+}
+
+fn ask_for_date(prompt: &str) -> Option<u64> {
+	Input::<String>::new()
+		.with_prompt(prompt)
+		.allow_empty(true)
+		.validate_with(|input: &String| -> Result<(), String> {
+			match input.trim().is_empty() {
+				true => Ok(()),
+				false => history::parse_date(input).map(|_| ()),
+			}
+		})
+		.interact_text()
+		.ok()
+		.and_then(|input| history::parse_date(&input).ok())
+}
+
+fn ask_for_history_filter() -> HistoryFilter {
+	let mut kinds = vec!["Alle".to_string()];
+	kinds.extend(Kind::ALL.iter().map(Kind::to_string));
+	let kind = Select::new()
+		.with_prompt("Art")
+		.items(&kinds)
+		.default(0)
+		.interact_opt()
+		.unwrap_or(None)
+		.and_then(|index| index.checked_sub(1))
+		.map(|index| Kind::ALL[index]);
+	let player: String = Input::new()
+		.with_prompt("Spieler (leer für alle)")
+		.allow_empty(true)
+		.interact_text()
+		.unwrap_or_default();
+
+	HistoryFilter {
+		kind,
+		player: Some(player.trim().to_string()).filter(|player| !player.is_empty()),
+		since: ask_for_date("Ab Tag (z.B. 19.10.2026, leer für beliebig)"),
+		until: ask_for_date("Bis einschließlich Tag (leer für beliebig)"),
+		session: None,
+	}
+}
+
+fn browse_history(history: &mut History) {
+	let mut filter = HistoryFilter::default();
+	loop {
+		dbgprintln!("Filter: {}", filter);
+		let player = format!("Spieler wechseln ({})", history.player.as_deref().unwrap_or("keiner"));
+		let actions = ["Würfe anzeigen", "Filter setzen", "Filter zurücksetzen", player.as_str(), "Zurück"];
+		let action = Select::new()
+			.with_prompt("Verlauf")
+			.items(actions)
+			.default(0)
+			.interact_opt()
+			.unwrap_or(None);
+		match action {
+			Some(0) => {
+				let entries = history.load();
+				let found = filter.apply(&entries);
+				if found.is_empty() {
+					dbgprintln!("Keine passenden Würfe im Verlauf");
+					continue;
+				}
+				// Newest first
+				let items: Vec<String> = found.iter()
+					.rev()
+					.map(|(number, entry)| {
+						let player = entry.player.as_deref().map(|player| format!(" | {}", player)).unwrap_or_default();
+						format!("{}. {} | {}: {}{}", number, history::format_timestamp(entry.timestamp), entry.kind, entry.input, player)
+					})
+					.collect();
+				let selection = FuzzySelect::new()
+					.with_prompt("Wurf suchen")
+					.items(&items)
+					.default(0)
+					.interact_opt()
+					.unwrap_or(None);
+				if let Some(index) = selection {
+					let (number, entry) = found[found.len() - 1 - index];
+					dbgprintln!("{}. {}\n", number, entry);
+				}
+			},
+			Some(1) => filter = ask_for_history_filter(),
+			Some(2) => filter = HistoryFilter::default(),
+			Some(3) => {
+				let player: String = Input::new()
+					.with_prompt("Spieler (leer für keinen)")
+					.with_initial_text(history.player.clone().unwrap_or_default())
+					.allow_empty(true)
+					.interact_text()
+					.unwrap_or_default();
+				history.player = Some(player.trim().to_string()).filter(|player| !player.is_empty());
+			},
+			_ => break,
+		}
+	}
+}
+
+fn show_statistics(history: &History, colored_dice: &ColoredDices) {
+	let scopes = ["Diese Sitzung", "Gesamter Verlauf"];
+	let entries = match Select::new()
+		.with_prompt("Statistik")
+		.items(scopes)
+		.default(0)
+		.interact_opt()
+		.unwrap_or(None) {
+		Some(0) => history.session(),
+		Some(_) => history.load(),
+		None => return,
+	};
+	if entries.is_empty() {
+		dbgprintln!("Es wurde noch nicht gewürfelt");
+		return;
+	}
+	let entries: Vec<&Entry> = entries.iter().collect();
+	dbgprintln!("{}\n", Report::new(&entries, colored_dice));
 }
 
 fn get_app() -> Command {
@@ -205,45 +892,92 @@ fn get_app() -> Command {
 			.help("Verwendet eine Zahlen eingabe anstatt einer Auswahl und Anzahl von farbigen würfeln")
 			.action(clap::ArgAction::SetTrue)
 		)
+		.arg(Arg::new("physical")
+			.long("physical")
+			.conflicts_with_all(["repl", "tui"])
+			.help("Echte Würfel: statt zu würfeln werden die gewürfelten Augen eingegeben und ausgewertet, nur im Menü")
+			.action(clap::ArgAction::SetTrue)
+		)
+		.arg(Arg::new("player")
+			.long("player")
+			.help("Name des Spielers, unter dem die Würfe im Verlauf gespeichert werden")
+		)
+		.arg(Arg::new("repl")
+			.long("repl")
+			.help("Eingabezeile mit Befehlen wie r 3d6 oder spell kampf statt des Menüs")
+			.action(clap::ArgAction::SetTrue)
+		)
+		.arg(Arg::new("tui")
+			.long("tui")
+			.conflicts_with("repl")
+			.help("Vollbild mit Eingabe, Würfen, Statistik, farbigen Würfeln und Zerfallsreihe statt des Menüs")
+			.action(clap::ArgAction::SetTrue)
+		)
+		.arg(Arg::new("no history")
+			.long("no-history")
+			.global(true)
+			.help("Schreibt die Würfe nicht in den Verlauf")
+			.action(clap::ArgAction::SetTrue)
+		)
+		.arg(Arg::new("seed")
+			.long("seed")
+			.global(true)
+			.value_parser(clap::value_parser!(Seed))
+			.help("Startwert für den Zufall, eine Zahl oder 64 Hex Ziffern, mit --audit nur 64 Hex Ziffern. Mit dem gleichen Seed sind alle Würfe gleich")
+		)
+		.arg(Arg::new("rng")
+			.long("rng")
+			.global(true)
+			.value_parser(clap::value_parser!(Backend))
+			.help("Zufallsgenerator, nicht verfügbare fallen auf hardware -> os -> std zurück")
+		)
+		.arg(Arg::new("audit")
+			.long("audit")
+			.global(true)
+			.help("Gibt zu Beginn ein Commitment des Seeds aus und deckt den Seed am Ende auf, siehe verify")
+			.action(clap::ArgAction::SetTrue)
+		)
+		.arg(Arg::new("sub seeds")
+			.long("sub-seeds")
+			.global(true)
+			.help("Gibt vor jedem Wurf einen eigenen Seed aus, mit dem sich der Wurf wiederholen lässt")
+			.action(clap::ArgAction::SetTrue)
+		)
+		.arg(Arg::new("format")
+			.long("format")
+			.global(true)
+			.value_parser(clap::value_parser!(Format))
+			.help("Ausgabeformat der Unterbefehle und der Befehle in --repl, das Menü und --tui zeigen immer Text. Fehler und Hinweise gehen immer auf stderr")
+		)
+		.subcommand(commands::roll::command())
+		.subcommand(commands::colored::command())
+		.subcommand(commands::crit::command())
+		.subcommand(commands::spell::command())
+		.subcommand(commands::disadvantage::command())
+		.subcommand(commands::table::command())
+		.subcommand(commands::decay::command())
+		.subcommand(commands::history::command())
+		.subcommand(commands::stats::command())
+		.subcommand(commands::verify::command())
 }
 
 fn roll_colored_dice(
 	colored_dice: &ColoredDices,
 	error_message: &str,
 	number_instead: bool,
+	physical: bool,
 	stderr: &Term,
+	history: &History,
 	rng: &mut impl Rng
 ) -> io::Result<()> {
-	if number_instead {
+	// Without an amount any number of physical dice can be entered
+	let selection: Vec<(&ColoredDice, Option<usize>)> = if number_instead {
 		//Input a number and auto compute values
 		let amount = ask_for_amount(error_message, "Farbiger Würfel Wert");
-		//Tuple of value, amount and result
-		let mut dices: Vec<(u64, String)> = Vec::with_capacity(amount);
-		let mut remaining = amount;
-
-		let mut copied_dices: Vec<ColoredDice> = colored_dice.dices.to_vec();
-
-		copied_dices.sort_by(|a, b| a.value.cmp(&b.value).reverse());
-		for dice in copied_dices {
-			let result: u64 = (0..remaining / dice.value as usize)
-				.map(|_| dice.roll(rng) as u64)
-				.sum();
-			dices.push((result, dice.long));
-			remaining %= dice.value as usize;
-		}
-
-		let accumulated_result: u64 = dices.iter()
-			.map(|x| x.0)
-			.sum();
-		for result in dices {
-			dbgprintln!("{}: {}", result.1, result.0);
-		}
-
-		dbgprintln!(
-			"Insgesamt: {} ({})",
-			accumulated_result,
-			accumulated_result * 10
-		);
+		colored_dice.split_value(amount)
+			.into_iter()
+			.map(|(dice, amount)| (dice, Some(amount)))
+			.collect()
 	} else {
 		// Use multiselect...
 		let possibilities: Vec<&str> = colored_dice.dices.iter().map(|dice| &*dice.long).collect();
@@ -260,73 +994,178 @@ fn roll_colored_dice(
 			return Ok(());
 		}
 
-		let mut result: Vec<(&String, u64)> = Vec::with_capacity(selection.len());
-		let mut accumulated_amount: u64 = 0;
-		let selection = selection.into_iter()
+		selection.into_iter()
 			.filter_map(|select| colored_dice.dices.get(select))
-			.map(|dice| (dice, ask_for_amount(error_message, &*format!("Anzahl {}", dice.long))));
-		for (dice, amount) in selection {
-			let amount = (0..amount)
-				.map(|_| dice.roll(rng) as u64)
-				.sum();
-			accumulated_amount += amount;
-			result.push((&dice.long, amount))
-		}
+			.map(|dice| (dice, (!physical).then(|| ask_for_amount(error_message, &format!("Anzahl {}", dice.long)))))
+			.collect()
+	};
 
-		for res in result {
-			dbgprintln!("{}: {}", res.0, res.1);
-			dbgprintln!(
-				"Insgesamt: {} ({})",
-				accumulated_amount,
-				accumulated_amount * 10
-			);
+	let result: Vec<(&ColoredDice, usize, u64)> = if physical {
+		let entered: Option<Vec<(&ColoredDice, usize, u64)>> = selection.into_iter()
+			.filter(|(_, amount)| *amount != Some(0))
+			.map(|(dice, amount)| {
+				let prompt = match amount {
+					Some(amount) => format!("Augen {} ({} Würfel)", dice.long, amount),
+					None => format!("Augen {}", dice.long),
+				};
+				ask_for_faces(&prompt, |faces| match amount {
+					Some(amount) if faces.len() != amount => Err(format!("Es müssen {} Augenzahlen sein", amount)),
+					_ => dice.sum_faces(&faces).map(|sum| (faces.len(), sum)),
+				}).map(|(amount, sum)| (dice, amount, sum))
+			})
+			.collect();
+		match entered {
+			Some(entered) => entered,
+			None => return Ok(()),
 		}
+	} else {
+		let selection: Vec<(&ColoredDice, usize)> = selection.into_iter()
+			.map(|(dice, amount)| (dice, amount.unwrap_or_default()))
+			.collect();
+		ColoredDices::roll_selection(&selection, rng)
+	};
+
+	let report = ColoredReport::new(&result);
+	for dice in &report.dice {
+		dbgprintln!("{}: {}", dice.name, dice.result);
+	}
+	dbgprintln!(
+		"Insgesamt: {} ({})",
+		report.total,
+		report.value
+	);
+	let input: String = report.dice.iter()
+		// Also the dice which were not rolled, so the selection can be rolled again
+		.map(|dice| format!("{}{}", dice.amount, dice.short))
+		.collect();
+	if physical {
+		history.record_entered(Kind::Colored, input, &report, &report);
+	} else {
+		history.record(Kind::Colored, input, &report, &report);
 	}
 	Ok(())
 }
 
+fn crit(crits: &CritDices, count: i16, physical: bool, history: &History, rng: &mut impl Rng) {
+	if !physical {
+		let result = crits.roll(count, rng);
+		history.record(Kind::Crit, count.to_string(), &result, &result);
+		return;
+	}
+	let level_faces = ask_for_faces(&format!("W100 für die Level ({} Stück)", crits.level.len()), |faces| {
+		crits.check_level_faces(&faces).map(|_| faces)
+	});
+	let Some(level_faces) = level_faces else {
+		return;
+	};
+	let dice: Vec<String> = crits.dice_for(count).iter().map(|dice| dice.value.to_string()).collect();
+	let result = if dice.is_empty() {
+		crits.evaluate_faces(count, &level_faces, &[]).ok()
+	} else {
+		ask_for_faces(&format!("Augen der Crit Würfel (Werte {})", dice.join(", ")), |faces| {
+			crits.evaluate_faces(count, &level_faces, &faces)
+		})
+	};
+	if let Some(result) = result {
+		dbgprintln!("{}", result);
+		history.record_entered(Kind::Crit, count.to_string(), &result, &result);
+	}
+}
+
 fn main() -> io::Result<()> {
 	let matches = get_app().get_matches();
+	// Entering faces is only possible in the menu, the subcommands roll
+	if matches.get_flag("physical") && let Some((name, _)) = matches.subcommand() {
+		get_app().error(ErrorKind::ArgumentConflict, format!("--physical kann nicht mit {} verwendet werden, nur im Menü", name)).exit();
+	}
 
 	#[cfg(debug_assertions)]
 	let start: SystemTime = SystemTime::now();
 	#[cfg(debug_assertions)]
-	dbgprintln!("Loading Configuration");
+	edbgprintln!("Loading Configuration");
 
 	let preferences = Settings::load(None);
 	let colored_dice = ColoredDices::load(None);
 	let normal_dices = Dices::load(None);
 	let operation = Operation::load(None);
+	let classifications = Classification::load(None);
+	let bestiary = BestiaryEntry::load(None);
 	let spells = Spells::load(None);
 	let disadvantages: Vec<Disadvantage> = Disadvantage::load(None);
+	let advantages: Vec<Advantage> = Advantage::load(None);
 	let crits = CritDices::load(None);
+	let tables = table::load_all();
 
 	#[cfg(debug_assertions)]
 	match start.elapsed() {
-		Ok(elapsed) => dbgprintln!("Loading Configuration finished, took {} ms", elapsed.as_millis()),
+		Ok(elapsed) => edbgprintln!("Loading Configuration finished, took {} ms", elapsed.as_millis()),
 		Err(err) => edbgprintln!("{}", err)
 	}
 
-	let mut rng = {
-		let mut seed_value= 0;
-		#[cfg(target_arch = "x86_64")]
-		if is_x86_feature_detected!("rdseed") {
-			unsafe { _rdseed64_step(&mut seed_value); }
-		}
-
-		// rdseed failed (or this is not an x86-64 system, result is 0 also in bad systems that return success on failure (AMD)
-		if seed_value == 0 {
-			// C style initialization
-			seed_value = SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos() as u64;
-		}
-		StdRng::seed_from_u64(seed_value)
+	let backend = matches.get_one::<Backend>("rng").copied().unwrap_or(preferences.rng);
+	let seed = matches.get_one::<Seed>("seed").copied().or(preferences.seed);
+	let mut seeds = if matches.get_flag("audit") || preferences.audit {
+		Seeds::audited(backend, seed).unwrap_or_else(|err| get_app().error(ErrorKind::ArgumentConflict, err).exit())
+	} else {
+		Seeds::new(backend, seed, matches.get_flag("sub seeds") || preferences.sub_seeds)
 	};
+	let mut history = History::new(
+		matches.get_one::<String>("player").cloned().or(preferences.player.clone()),
+		!(matches.get_flag("no history") || preferences.no_history),
+	);
+	history.commitment = seeds.commitment().map(str::to_string);
 
 	let old = matches.get_flag("old_style") || preferences.old_style;
 	let no_dice_select = matches.get_flag("no select dice select") || preferences.no_select_dice_select;
 	let number_instead = matches.get_flag("number instead") || preferences.number_instead;
 	let no_tutorial = matches.get_flag("no tutorial") || preferences.no_tutorial;
 	let no_summary_message = matches.get_flag("no summary message") || preferences.no_summary_message;
+	let physical = matches.get_flag("physical") || preferences.physical;
+	let menu = matches.subcommand().is_none() && !(matches.get_flag("tui") || preferences.tui || matches.get_flag("repl") || preferences.repl);
+	if physical && !menu {
+		edbgprintln!("physical aus den Einstellungen gilt nur im Menü, es wird gewürfelt");
+	}
+
+	let format = matches.get_one::<Format>("format").copied().unwrap_or(preferences.format);
+
+	// Subcommands print their result once and exit instead of starting the interactive menu
+	if let Some((name, sub_matches)) = matches.subcommand() {
+		// Reading the history is no roll, so it does not use up a sub-seed
+		match name {
+			"history" => exit(commands::history::run(sub_matches, &history, format)),
+			"stats" => exit(commands::stats::run(sub_matches, &history, &colored_dice, format)),
+			"verify" => {
+				let rules = Rules {
+					colored_dice: &colored_dice,
+					crits: &crits,
+					tables: &tables,
+					spells: &spells,
+					operations: &operation,
+					classifications: &classifications,
+				};
+				exit(commands::verify::run(sub_matches, &rules, &history, format))
+			},
+			_ => {},
+		}
+		edbgprintln!("{}", seeds.report());
+		let mut rng = history.next_rng(&mut seeds);
+		let history = &history;
+		let code = match name {
+			"roll" => commands::roll::run(sub_matches, &normal_dices, old, no_summary_message, format, history, &mut rng),
+			"colored" => commands::colored::run(sub_matches, &colored_dice, format, history, &mut rng),
+			"crit" => commands::crit::run(sub_matches, &crits, format, history, &mut rng),
+			"spell" => commands::spell::run(sub_matches, &spells, format, history, &mut rng),
+			"disadvantage" => commands::disadvantage::run(sub_matches, &disadvantages, format, history, &mut rng),
+			"table" => commands::table::run(sub_matches, &tables, format, history, &mut rng),
+			"decay" => commands::decay::run(sub_matches, &operation, &classifications, format, history, &mut rng),
+			_ => commands::EXIT_USAGE,
+		};
+		if let Some(reveal) = seeds.reveal() {
+			edbgprintln!("{}", reveal);
+		}
+		exit(code);
+	}
+	edbgprintln!("{}", seeds.report());
 
 	#[cfg(debug_assertions)]
 	let error_message = format!(
@@ -344,29 +1183,65 @@ fn main() -> io::Result<()> {
 		dbgprintln!("{:?}\n", colored_dice);
 		dbgprintln!("{:?}\n", normal_dices);
 		dbgprintln!("{:?}\n", operation);
+		dbgprintln!("{:?}\n", classifications);
+		dbgprintln!("{:?}\n", bestiary);
 		dbgprintln!("{:?}\n", spells);
 		dbgprintln!("{:?}\n", disadvantages);
+		dbgprintln!("{:?}\n", advantages);
 
 	}
 
-	let mut finished = false;
-	if !no_tutorial {
+	// The REPL and the dashboard replace the menu, the session ends the same way
+	let tui = matches.get_flag("tui") || preferences.tui;
+	let repl = !tui && (matches.get_flag("repl") || preferences.repl);
+	let mut finished = repl || tui;
+	if finished {
+		let engines = Repl {
+			colored_dice: &colored_dice,
+			normal_dices: &normal_dices,
+			crits: &crits,
+			spells: &spells,
+			disadvantages: &disadvantages,
+			tables: &tables,
+			operations: &operation,
+			classifications: &classifications,
+			old_style: old,
+			no_summary: no_summary_message,
+			format,
+		};
+		if !tui {
+			engines.run(&mut history, &mut seeds);
+		} else if let Err(err) = tui::run(&engines, &mut history, &mut seeds) {
+			edbgprintln!("Das Vollbild konnte nicht gestartet werden: {}", err);
+		}
+	} else if !no_tutorial {
 		print_startup_information(&colored_dice, &normal_dices);
 	}
 
 	let stdout = Term::stdout();
 	let stderr = Term::stderr();
 	let stdin = io::stdin();
-	let items = vec![
+	let mut items: Vec<String> = [
 		"Farbiger Würfel",
 		"Normaler Würfel",
 		"Crit",
 		"Zerfallsreihen",
+		"Zerfallsreihe fortsetzen",
+		"Zufällige Zerfallsreihe",
+		"Zerfallsreihe exportieren",
+		"Bestiarium",
 		"Random Zauber",
+		"Zauberbuch",
 		"Random Nachteil",
-		"Hilfe",
-		"Verlassen",
-	];
+		"Random Vorteil",
+		"Random Vorteil und Nachteil",
+		"Verlauf",
+		"Statistik",
+	].into_iter().map(String::from).collect();
+	// Every table in the tables folder gets its own entry
+	items.extend(tables.iter().map(Table::label));
+	items.push("Hilfe".to_string());
+	items.push("Verlassen".to_string());
 
 	while !finished {
 		let selection = Select::new()
@@ -376,7 +1251,7 @@ fn main() -> io::Result<()> {
 				.ok()
 				.flatten()
 				.and_then(|sel| items.get(sel))
-				.copied();
+				.cloned();
 
 		let answer = match selection {
 			Some(answer) => answer,
@@ -386,13 +1261,16 @@ fn main() -> io::Result<()> {
 			}
 		};
 
-		match answer {
+		match answer.as_str() {
 			"Farbiger Würfel" => {
+				let mut rng = history.next_rng(&mut seeds);
 				if let Err(err) = roll_colored_dice(
 					&colored_dice,
 					&error_message,
 					number_instead,
+					physical,
 					&stderr,
+					&history,
 					&mut rng,
 				) {
 					edbgprintln!("{}", err);
@@ -400,15 +1278,7 @@ fn main() -> io::Result<()> {
 				}
 			},
 			"Hilfe" => {
-				finished = handle_input(
-					"h",
-					old,
-					&colored_dice,
-					&normal_dices,
-					&error_message,
-					no_summary_message,
-					&mut rng,
-				);
+				print_startup_information(&colored_dice, &normal_dices);
 			},
 			"Crit" => {
 				let input = Input::new()
@@ -419,7 +1289,7 @@ fn main() -> io::Result<()> {
 							return Err("Bitte etwas eingeben");
 						}
 
-						i16::from_str_radix(new_val, 10)
+						new_val.parse::<i16>()
 							.ok()
 							.filter(|&val| val >= 0)
 							.map(|_| ())
@@ -430,7 +1300,10 @@ fn main() -> io::Result<()> {
 					.and_then(|inp| inp.parse::<i16>().map_err(|err| err.to_string()));
 
 				match input {
-					Ok(count) => crits.roll(count, &mut rng),
+					Ok(count) => {
+						let mut rng = history.next_rng(&mut seeds);
+						crit(&crits, count, physical, &history, &mut rng);
+					},
 					Err(err) => eprintln!("{}", err),
 				}
 			},
@@ -438,26 +1311,66 @@ fn main() -> io::Result<()> {
 				finished = true;
 			},
 			"Zerfallsreihen" => {
-				decay_series(&stdout, &operation);
+				let session = Session::new(ask_for_state(&stdout));
+				let mut rng = history.next_rng(&mut seeds);
+				decay_series(&stdout, session, &operation, &classifications, &bestiary, &history, &mut rng);
+			},
+			"Zerfallsreihe fortsetzen" => {
+				let mut rng = history.next_rng(&mut seeds);
+				resume_decay_series(&stdout, &operation, &classifications, &bestiary, &history, &mut rng);
+			},
+			"Zufällige Zerfallsreihe" => {
+				let mut rng = history.next_rng(&mut seeds);
+				random_decay_series(&stdout, &operation, &classifications, &bestiary, &error_message, &history, &mut rng);
+			},
+			"Zerfallsreihe exportieren" => {
+				export_decay_series(&stdout, &operation, &classifications, &error_message);
+			},
+			"Bestiarium" => {
+				browse_bestiary(&bestiary);
 			},
 			"Random Zauber" => {
-				let search_string = "Kampfzauber";
-				let items: Vec<&str> = spells.iter().map(|x| &*x.name).collect();
-				let default = items.iter().position(|x| *x == search_string).unwrap_or_else(|| 0);
-
-				let selection = Select::new()
-					.items(&items)
-					.default(default)
-					.interact_opt()
-					.unwrap_or_else(|_| None);
-
-				if let Some(index) = selection {
-					dbgprintln!("{}", spells[index].roll(&mut rng))
-				}
+				let mut rng = history.next_rng(&mut seeds);
+				roll_spells(&spells, &error_message, &history, &mut rng);
+			},
+			"Zauberbuch" => {
+				browse_spells(&spells, &error_message);
 			},
 			"Random Nachteil" => {
-				let rando = disadvantage::get_random(&disadvantages, &mut rng);
-				dbgprintln!("{}", rando);
+				let mut rng = history.next_rng(&mut seeds);
+				roll_disadvantage(&disadvantages, &error_message, &history, &mut rng);
+			},
+			"Random Vorteil" => {
+				match disadvantage::get_random(&advantages, &mut history.next_rng(&mut seeds)) {
+					Some(rando) => {
+						dbgprintln!("{}", rando);
+						history.record(Kind::Advantage, "1", &rando, &rando);
+					},
+					None => dbgprintln!("Es gibt keine Vorteile"),
+				}
+			},
+			"Random Vorteil und Nachteil" => {
+				// Advantages have no IP, so both are drawn on their own and the pair is not balanced by value
+				let mut rng = history.next_rng(&mut seeds);
+				match (disadvantage::get_random(&advantages, &mut rng), disadvantage::get_random(&disadvantages, &mut rng)) {
+					(Some(advantage), Some(disadvantage)) => {
+						dbgprintln!("Vorteil: {}\n\nNachteil: {}", advantage, disadvantage);
+						history.record(Kind::Advantage, "1", &advantage, &advantage);
+						let report = DisadvantageReport::new(std::slice::from_ref(&disadvantage));
+						history.record(Kind::Disadvantage, "1", &report, &report);
+					},
+					_ => dbgprintln!("Es gibt keine Vorteile oder keine Nachteile"),
+				}
+			},
+			"Verlauf" => {
+				browse_history(&mut history);
+			},
+			"Statistik" => {
+				show_statistics(&history, &colored_dice);
+			},
+			label if label.starts_with("Tabelle: ") => {
+				let mut rng = history.next_rng(&mut seeds);
+				roll_table(&tables, label, &history, &mut rng);
 			},
 			_ => {
 				dbgprint!("Seitenanzahl: ");
@@ -469,14 +1382,17 @@ fn main() -> io::Result<()> {
 					let mut input = String::new();
 					match stdin.read_line(&mut input) {
 						Ok(_) => {
+							let mut rng = history.next_rng(&mut seeds);
 							// Return value determines continuation of the loop, true ends the loop, false continues it
 							finished = handle_input(
-								&*input.replace("\n", ""),
+								&input.replace("\n", ""),
 								old,
+								physical,
 								&colored_dice,
 								&normal_dices,
 								&error_message,
 								no_summary_message,
+								&history,
 								&mut rng,
 							);
 						}
@@ -488,17 +1404,20 @@ fn main() -> io::Result<()> {
 						.items(&dice_items)
 						.default(3)
 						.interact_on_opt(&Term::stderr())
-						.unwrap_or_else(|_| None)
+						.unwrap_or(None)
 						.and_then(|index| dice_items.get(index));
 
 					if let Some(input) = selection {
+						let mut rng = history.next_rng(&mut seeds);
 						finished = handle_input(
 							input,
 							old,
+							physical,
 							&colored_dice,
 							&normal_dices,
 							error_message.as_str(),
 							no_summary_message,
+							&history,
 							&mut rng,
 						);
 					}
@@ -506,5 +1425,14 @@ fn main() -> io::Result<()> {
 			}
 		}
 	}
+
+	let session = history.session();
+	if !session.is_empty() {
+		let entries: Vec<&Entry> = session.iter().collect();
+		dbgprintln!("Statistik der Sitzung:\n{}", Report::new(&entries, &colored_dice));
+	}
+	if let Some(reveal) = seeds.reveal() {
+		edbgprintln!("{}", reveal);
+	}
 	Ok(())
 }
@@ -0,0 +1,49 @@
+use crate::edbgprintln;
+use clap::builder::PossibleValue;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/**
+How the subcommands print their results. `json` and `yaml` print exactly one document on stdout,
+everything else (errors, notes) goes to stderr
+ */
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+	#[default]
+	Text,
+	Json,
+	Yaml,
+}
+
+impl ValueEnum for Format {
+	fn value_variants<'a>() -> &'a [Self] {
+		&[Format::Text, Format::Json, Format::Yaml]
+	}
+
+	fn to_possible_value(&self) -> Option<PossibleValue> {
+		Some(match self {
+			Format::Text => PossibleValue::new("text").help("Deutscher Fließtext"),
+			Format::Json => PossibleValue::new("json"),
+			Format::Yaml => PossibleValue::new("yaml"),
+		})
+	}
+}
+
+/**
+Prints `value` in the machine readable formats, `text` is called for the human readable one
+ */
+pub fn print<T: Serialize>(format: Format, value: &T, text: impl FnOnce()) {
+	let serialized = match format {
+		Format::Text => {
+			text();
+			return;
+		},
+		Format::Json => serde_json::to_string_pretty(value).map_err(|err| err.to_string()),
+		Format::Yaml => serde_yaml::to_string(value).map_err(|err| err.to_string()),
+	};
+	match serialized {
+		Ok(serialized) => println!("{}", serialized.trim_end()),
+		Err(err) => edbgprintln!("{}", err),
+	}
+}
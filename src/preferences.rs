@@ -4,7 +4,9 @@ use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::{BufReader, BufWriter};
 use std::path::Path;
-use crate::dbgprintln;
+use crate::edbgprintln;
+use crate::output::Format;
+use crate::rng::{Backend, Seed};
 
 const PREFERENCE_FILE: &str = "settings.yaml";
 
@@ -15,6 +17,36 @@ pub struct Settings {
 	pub(crate) no_summary_message: bool,
 	pub(crate) no_select_dice_select: bool,
 	pub(crate) number_instead: bool,
+	/// Output format of the subcommands and the REPL, can be overridden with --format. The menu and the TUI always show text
+	#[serde(default)]
+	pub(crate) format: Format,
+	/// Physical dice, the faces are entered instead of rolled
+	#[serde(default)]
+	pub(crate) physical: bool,
+	/// Name under which the rolls are written to the history, can be overridden with --player
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub(crate) player: Option<String>,
+	/// Nothing is written to the history
+	#[serde(default)]
+	pub(crate) no_history: bool,
+	/// Starts the REPL instead of the menu
+	#[serde(default)]
+	pub(crate) repl: bool,
+	/// Starts the full-screen dashboard instead of the menu, wins over `repl`
+	#[serde(default)]
+	pub(crate) tui: bool,
+	/// Commits to the seed at the start and reveals it at the end, see `würfeln verify`
+	#[serde(default)]
+	pub(crate) audit: bool,
+	/// Source of randomness, can be overridden with --rng
+	#[serde(default)]
+	pub(crate) rng: Backend,
+	/// Fixed seed for all rolls, without one every start is different
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub(crate) seed: Option<Seed>,
+	/// Shows the sub-seed of every roll
+	#[serde(default)]
+	pub(crate) sub_seeds: bool,
 }
 
 impl Default for Settings {
@@ -25,6 +57,16 @@ impl Default for Settings {
 			no_summary_message: false,
 			no_select_dice_select: false,
 			number_instead: true,
+			format: Format::Text,
+			physical: false,
+			player: None,
+			no_history: false,
+			repl: false,
+			tui: false,
+			audit: false,
+			rng: Backend::Std,
+			seed: None,
+			sub_seeds: false,
 		}
 	}
 }
@@ -38,7 +80,7 @@ impl Loadable<Self> for Settings {
 			let file = File::open(file_name).unwrap();
 			let buf_reader = BufReader::new(file);
 			let parsed = serde_yaml::from_reader::<BufReader<File>, Settings>(buf_reader);
-			parsed.unwrap_or(Settings::default())
+			parsed.unwrap_or_default()
 		} else {
 			match File::create(file_name) {
 				Ok(file) => {
@@ -48,15 +90,15 @@ impl Loadable<Self> for Settings {
 						&Settings::default(),
 					) {
 						Ok(_) => {
-							dbgprintln!("Neue Einstellungen wurden erzeugt");
+							edbgprintln!("Neue Einstellungen wurden erzeugt");
 						}
 						Err(err) => {
-							dbgprintln!("{}", Colour::RGB(255, 0, 0).paint(err.to_string()));
+							edbgprintln!("{}", Colour::RGB(255, 0, 0).paint(err.to_string()));
 						}
 					}
 				}
 				Err(err) => {
-					dbgprintln!("{}", Colour::RGB(255, 0, 0).paint(err.to_string()));
+					edbgprintln!("{}", Colour::RGB(255, 0, 0).paint(err.to_string()));
 				}
 			}
 
@@ -0,0 +1,389 @@
+use crate::commands;
+use crate::common::settings_path;
+use crate::decay_series::{Classification, Operation};
+use crate::dice::colored_dice::ColoredDices;
+use crate::dice::crit_dice::CritDices;
+use crate::dice::normal_dice::Dices;
+use crate::disadvantage::Disadvantage;
+use crate::history::History;
+use crate::output::Format;
+use crate::rng::Seeds;
+use crate::spell::Spells;
+use crate::table::Table;
+use crate::{dbgprintln, edbgprintln};
+use clap::{Arg, ArgMatches, Command};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+/// Entered commands of earlier sessions, in the data directory
+const COMMAND_HISTORY_FILE: &str = "repl_history.txt";
+
+const PROMPT: &str = "würfeln> ";
+
+/**
+The command language, the rolls are the subcommands of the command line with short aliases
+ */
+pub fn command() -> Command {
+	Command::new("würfeln")
+		.no_binary_name(true)
+		.disable_help_subcommand(true)
+		.help_template("Befehle:\n{subcommands}")
+		.subcommand(commands::roll::command().visible_alias("r"))
+		.subcommand(commands::colored::command().visible_alias("c"))
+		.subcommand(commands::crit::command())
+		.subcommand(commands::spell::command())
+		.subcommand(commands::table::command().visible_alias("t"))
+		.subcommand(commands::disadvantage::command())
+		.subcommand(commands::decay::command())
+		.subcommand(commands::history::command())
+		.subcommand(commands::stats::command())
+		.subcommand(Command::new("undo")
+			.about("Entfernt den letzten Wurf dieser Sitzung aus dem Verlauf")
+		)
+		.subcommand(Command::new("help")
+			.about("Zeigt die Befehle oder die Hilfe eines Befehls")
+			.arg(Arg::new("command"))
+		)
+		.subcommand(Command::new("exit")
+			.visible_alias("quit")
+			.about("Beendet würfeln")
+		)
+}
+
+/**
+Splits a line into words at whitespace, words in double quotes may contain whitespace
+ */
+pub fn split(line: &str) -> Result<Vec<String>, String> {
+	let mut words = vec![];
+	let mut word: Option<String> = None;
+	let mut quoted = false;
+	for char in line.chars() {
+		match char {
+			'"' => {
+				quoted = !quoted;
+				word.get_or_insert_with(String::new);
+			},
+			char if char.is_whitespace() && !quoted => words.extend(word.take()),
+			char => word.get_or_insert_with(String::new).push(char),
+		}
+	}
+	if quoted {
+		return Err("Ein \" wurde nicht geschlossen".to_string());
+	}
+	words.extend(word);
+	Ok(words)
+}
+
+/// Quotes names with whitespace, so they stay one word
+pub fn quote(name: &str) -> String {
+	if name.contains(char::is_whitespace) {
+		format!("\"{}\"", name)
+	} else {
+		name.to_string()
+	}
+}
+
+/**
+Alternative to the menu: reads commands like `r 3d6` or `spell kampf` until `exit`
+ */
+#[derive(Clone, Copy)]
+pub struct Repl<'a> {
+	pub colored_dice: &'a ColoredDices,
+	pub normal_dices: &'a Dices,
+	pub crits: &'a CritDices,
+	pub spells: &'a [Spells],
+	pub disadvantages: &'a [Disadvantage],
+	pub tables: &'a [Table],
+	pub operations: &'a [Operation],
+	pub classifications: &'a [Classification],
+	pub old_style: bool,
+	pub no_summary: bool,
+	pub format: Format,
+}
+
+impl Repl<'_> {
+	pub fn run(&self, history: &mut History, seeds: &mut Seeds) {
+		let mut editor: Editor<Self, DefaultHistory> = match Editor::new() {
+			Ok(editor) => editor,
+			Err(err) => {
+				edbgprintln!("Die Eingabezeile konnte nicht gestartet werden: {}", err);
+				return;
+			},
+		};
+		editor.set_helper(Some(*self));
+		let path = settings_path(COMMAND_HISTORY_FILE);
+		// Missing on the first start
+		let _ = editor.load_history(&path);
+		dbgprintln!("Befehle wie r 3d6, c 2r1s, crit 5 oder spell kampf, help zeigt alle");
+
+		loop {
+			let line = match editor.readline(PROMPT) {
+				Ok(line) => line,
+				Err(ReadlineError::Interrupted | ReadlineError::Eof) => break,
+				Err(err) => {
+					edbgprintln!("{}", err);
+					break;
+				},
+			};
+			if line.trim().is_empty() {
+				continue;
+			}
+			let _ = editor.add_history_entry(line.as_str());
+			if self.execute(&line, history, seeds) {
+				break;
+			}
+		}
+		if let Err(err) = editor.save_history(&path) {
+			edbgprintln!("{}: {}", path.display(), err);
+		}
+	}
+
+	/// Runs one line, returns whether the REPL should end
+	fn execute(&self, line: &str, history: &mut History, seeds: &mut Seeds) -> bool {
+		let matches = match split(line).map(|words| command().try_get_matches_from(words)) {
+			Ok(Ok(matches)) => matches,
+			Ok(Err(err)) => {
+				let _ = err.print();
+				return false;
+			},
+			Err(err) => {
+				edbgprintln!("{}", err);
+				return false;
+			},
+		};
+		let Some((name, matches)) = matches.subcommand() else {
+			return false;
+		};
+		match name {
+			"exit" => return true,
+			"help" => self.help(matches),
+			"undo" => match history.undo() {
+				Ok(entry) => dbgprintln!("Rückgängig gemacht: {}: {}", entry.kind, entry.input),
+				Err(err) => edbgprintln!("{}", err),
+			},
+			"history" => {
+				commands::history::run(matches, history, self.format);
+			},
+			"stats" => {
+				commands::stats::run(matches, history, self.colored_dice, self.format);
+			},
+			_ => {
+				// A command which can not roll does not get a generator
+				if let Err(err) = self.validate(name, matches) {
+					edbgprintln!("{}", err);
+					return false;
+				}
+				let mut rng = history.next_rng(seeds);
+				let history = &*history;
+				match name {
+					"roll" => commands::roll::run(matches, self.normal_dices, self.old_style, self.no_summary, self.format, history, &mut rng),
+					"colored" => commands::colored::run(matches, self.colored_dice, self.format, history, &mut rng),
+					"crit" => commands::crit::run(matches, self.crits, self.format, history, &mut rng),
+					"spell" => commands::spell::run(matches, self.spells, self.format, history, &mut rng),
+					"table" => commands::table::run(matches, self.tables, self.format, history, &mut rng),
+					"disadvantage" => commands::disadvantage::run(matches, self.disadvantages, self.format, history, &mut rng),
+					"decay" => commands::decay::run(matches, self.operations, self.classifications, self.format, history, &mut rng),
+					_ => commands::EXIT_USAGE,
+				};
+			},
+		}
+		false
+	}
+
+	/// Checks the arguments of a rolling command without rolling
+	pub fn validate(&self, name: &str, matches: &ArgMatches) -> Result<(), String> {
+		match name {
+			"roll" => commands::roll::dice(matches, self.normal_dices).map(drop),
+			"colored" => commands::colored::selection(matches, self.colored_dice).map(drop),
+			"spell" => commands::spell::categories(matches, self.spells).map(drop),
+			"table" => commands::table::find(matches, self.tables).map(drop),
+			"disadvantage" => commands::disadvantage::character(matches).map(drop),
+			"decay" => commands::decay::arguments(matches, self.operations).map(drop),
+			_ => Ok(()),
+		}
+	}
+
+	fn help(&self, matches: &ArgMatches) {
+		let mut command = command();
+		let help = match matches.get_one::<String>("command") {
+			Some(name) => match command.find_subcommand_mut(name) {
+				Some(subcommand) => subcommand.render_help(),
+				None => {
+					edbgprintln!("Unbekannter Befehl: {}", name);
+					return;
+				},
+			},
+			None => command.render_help(),
+		};
+		dbgprintln!("{}", help);
+	}
+
+	/// Start of the word before `line` ends and the words which can complete it
+	pub fn candidates(&self, line: &str) -> (usize, Vec<Pair>) {
+		// An open quote belongs to the current word
+		let start = match line.matches('"').count() % 2 {
+			1 => line.rfind('"').unwrap_or_default(),
+			// Whitespace like a no-break space takes more than one byte
+		_ => line.char_indices()
+			.rfind(|(_, c)| c.is_whitespace())
+			.map(|(index, c)| index + c.len_utf8())
+			.unwrap_or_default(),
+		};
+		let word = &line[start..];
+		let lowercase = word.to_lowercase();
+		let pair = |display: String, replacement: String| Pair { display, replacement };
+
+		let command = command();
+		let Some(name) = line[..start].split_whitespace().next() else {
+			let names = command.get_subcommands().flat_map(|subcommand| {
+				std::iter::once(subcommand.get_name()).chain(subcommand.get_visible_aliases())
+			});
+			let pairs = names
+				.filter(|name| name.starts_with(&lowercase))
+				.map(|name| pair(name.to_string(), name.to_string()))
+				.collect();
+			return (start, pairs);
+		};
+		let name = command.find_subcommand(name).map(Command::get_name).unwrap_or(name);
+
+		let digits: String = word.chars().take_while(char::is_ascii_digit).collect();
+		let pairs = match name {
+			"roll" => self.normal_dices.dices
+				.iter()
+				.map(|sides| format!("{}d{}", digits, sides))
+				.filter(|dice| dice.starts_with(&lowercase))
+				.map(|dice| pair(dice.clone(), dice))
+				.collect(),
+			// Adds the next die to a selection like 2r1
+			"colored" if word.ends_with(|char: char| char.is_ascii_digit()) => self.colored_dice.dices
+				.iter()
+				.map(|dice| pair(format!("{} ({})", dice.short, dice.long), format!("{}{}", word, dice.short)))
+				.collect(),
+			"table" => self.tables
+				.iter()
+				.map(|table| table.name.as_str())
+				.filter(|name| quote(name).to_lowercase().starts_with(&lowercase) || name.to_lowercase().starts_with(&lowercase))
+				.map(|name| pair(name.to_string(), quote(name)))
+				.collect(),
+			"spell" => self.spells
+				.iter()
+				.map(|category| category.name.as_str())
+				.filter(|name| quote(name).to_lowercase().starts_with(&lowercase) || name.to_lowercase().starts_with(&lowercase))
+				.map(|name| pair(name.to_string(), quote(name)))
+				.collect(),
+			"help" => command.get_subcommands()
+				.map(Command::get_name)
+				.filter(|name| name.starts_with(&lowercase))
+				.map(|name| pair(name.to_string(), name.to_string()))
+				.collect(),
+			_ => vec![],
+		};
+		(start, pairs)
+	}
+}
+
+impl Completer for Repl<'_> {
+	type Candidate = Pair;
+
+	fn complete(&self, line: &str, pos: usize, _: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+		Ok(self.candidates(&line[..pos]))
+	}
+}
+
+impl Hinter for Repl<'_> {
+	type Hint = String;
+}
+
+impl Highlighter for Repl<'_> {}
+
+impl Validator for Repl<'_> {}
+
+impl Helper for Repl<'_> {}
+
+#[cfg(test)]
+mod tests {
+	use crate::decay_series::{Classification, Operation};
+	use crate::dice::colored_dice::ColoredDices;
+	use crate::dice::crit_dice::CritDices;
+	use crate::dice::normal_dice::Dices;
+	use crate::history::History;
+	use crate::output::Format;
+	use crate::repl::{split, Repl};
+	use crate::rng::{Backend, Seed, Seeds};
+	use crate::spell::Spells;
+
+	#[test]
+	fn test_split() {
+		assert_eq!(split("r  3d6 1d20 ").unwrap(), vec!["r", "3d6", "1d20"]);
+		assert_eq!(split("spell \"Heil- und Schutzzauber\" -n 2").unwrap(), vec!["spell", "Heil- und Schutzzauber", "-n", "2"]);
+		assert_eq!(split("t \"\"").unwrap(), vec!["t", ""]);
+		assert!(split("spell \"Heil").is_err());
+	}
+
+	#[test]
+	fn test_candidates() {
+		let colored_dice = ColoredDices::default();
+		let spells = Spells::defaults();
+		let repl = Repl {
+			colored_dice: &colored_dice,
+			normal_dices: &Dices::default(),
+			crits: &CritDices::default(),
+			spells: &spells,
+			disadvantages: &[],
+			tables: &[],
+			operations: &Vec::<Operation>::new(),
+			classifications: &Vec::<Classification>::new(),
+			old_style: false,
+			no_summary: false,
+			format: Format::Text,
+		};
+		let replacements = |line: &str| -> (usize, Vec<String>) {
+			let (start, pairs) = repl.candidates(line);
+			(start, pairs.into_iter().map(|pair| pair.replacement).collect())
+		};
+
+		assert_eq!(replacements("cr"), (0, vec!["crit".to_string()]));
+		assert_eq!(replacements("r 3d1"), (2, vec!["3d10".to_string(), "3d100".to_string()]));
+		assert_eq!(replacements("spell ka"), (6, vec!["Kampfzauber".to_string()]));
+		assert_eq!(replacements("spell \"heil"), (6, vec!["\"Heil- und Schutzzauber\"".to_string()]));
+		assert_eq!(replacements("r\u{a0}3d1"), (3, vec!["3d10".to_string(), "3d100".to_string()]));
+		let (_, colored) = replacements("c 2r1");
+		assert_eq!(colored.len(), colored_dice.dices.len());
+		assert!(colored.iter().all(|selection| selection.starts_with("2r1")));
+	}
+
+	#[test]
+	fn test_execute() {
+		let repl = Repl {
+			colored_dice: &ColoredDices::default(),
+			normal_dices: &Dices::default(),
+			crits: &CritDices::default(),
+			spells: &Spells::defaults(),
+			disadvantages: &[],
+			tables: &[],
+			operations: &Vec::<Operation>::new(),
+			classifications: &Vec::<Classification>::new(),
+			old_style: false,
+			no_summary: true,
+			format: Format::Json,
+		};
+		let mut history = History::in_memory();
+		let mut seeds = Seeds::new(Backend::Std, Some(Seed::Short(0)), false);
+
+		// An invalid command does not get a generator, so the chain is the same as without it
+		assert!(!repl.execute("r 3d7", &mut history, &mut seeds));
+		assert!(!repl.execute("spell Unbekannt", &mut history, &mut seeds));
+		assert_eq!(seeds.last(), None);
+		assert!(history.session().is_empty());
+		assert!(!repl.execute("r 3d6", &mut history, &mut seeds));
+		let mut fresh = Seeds::new(Backend::Std, Some(Seed::Short(0)), false);
+		fresh.next_rng();
+		assert_eq!(history.session()[0].seed, fresh.last());
+		assert!(repl.execute("exit", &mut history, &mut seeds));
+	}
+}
@@ -0,0 +1,557 @@
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::{_rdrand64_step, _rdseed64_step};
+use crate::audit;
+use crate::edbgprintln;
+use clap::builder::PossibleValue;
+use clap::ValueEnum;
+use rand::rngs::{OsRng, StdRng};
+use rand::{RngCore, SeedableRng, TryRngCore};
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::SystemTime;
+
+/// How often a hardware instruction is tried before it counts as failed
+const HARDWARE_RETRIES: usize = 32;
+
+/**
+Source of randomness, the seedable ones can replay rolls with `--seed`
+ */
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+	#[default]
+	Std,
+	ChaCha20,
+	Os,
+	Hardware,
+}
+
+impl Backend {
+	fn seedable(&self) -> bool {
+		matches!(self, Backend::Std | Backend::ChaCha20)
+	}
+}
+
+impl ValueEnum for Backend {
+	fn value_variants<'a>() -> &'a [Self] {
+		&[Backend::Std, Backend::ChaCha20, Backend::Os, Backend::Hardware]
+	}
+
+	fn to_possible_value(&self) -> Option<PossibleValue> {
+		Some(match self {
+			Backend::Std => PossibleValue::new("std").help("StdRng, reproduzierbar"),
+			Backend::ChaCha20 => PossibleValue::new("chacha20").help("ChaCha20 mit 256 Bit Seed, reproduzierbar"),
+			Backend::Os => PossibleValue::new("os").help("Zufall des Betriebssystems"),
+			Backend::Hardware => PossibleValue::new("hardware").help("RDSEED oder RDRAND des Prozessors"),
+		})
+	}
+}
+
+impl Display for Backend {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Backend::Std => write!(f, "StdRng"),
+			Backend::ChaCha20 => write!(f, "ChaCha20"),
+			Backend::Os => write!(f, "Betriebssystem"),
+			Backend::Hardware => write!(f, "Hardware"),
+		}
+	}
+}
+
+/**
+A seed is either a number or 64 hex digits for the full 256 bit
+ */
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(try_from = "RawSeed", into = "RawSeed")]
+pub enum Seed {
+	Short(u64),
+	Full([u8; 32]),
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawSeed {
+	Number(u64),
+	Text(String),
+}
+
+impl TryFrom<RawSeed> for Seed {
+	type Error = String;
+
+	fn try_from(raw: RawSeed) -> Result<Self, Self::Error> {
+		match raw {
+			RawSeed::Number(seed) => Ok(Seed::Short(seed)),
+			RawSeed::Text(seed) => seed.parse(),
+		}
+	}
+}
+
+impl From<Seed> for RawSeed {
+	fn from(seed: Seed) -> Self {
+		match seed {
+			Seed::Short(seed) => RawSeed::Number(seed),
+			full => RawSeed::Text(full.to_string()),
+		}
+	}
+}
+
+impl Seed {
+	fn generator<R: SeedableRng<Seed = [u8; 32]>>(&self) -> R {
+		match self {
+			Seed::Short(seed) => R::seed_from_u64(*seed),
+			Seed::Full(seed) => R::from_seed(*seed),
+		}
+	}
+
+	/// The seed of the following roll, derived from a fresh generator so it does not depend on the roll
+	fn derive<R: SeedableRng<Seed = [u8; 32]> + RngCore>(&self) -> Seed {
+		let mut generator: R = self.generator();
+		match self {
+			Seed::Short(_) => Seed::Short(generator.next_u64()),
+			Seed::Full(_) => {
+				let mut seed = [0u8; 32];
+				generator.fill_bytes(&mut seed);
+				Seed::Full(seed)
+			},
+		}
+	}
+
+	/// Sub-seed of the roll after the one made with this seed
+	pub fn next(&self, backend: Backend) -> Seed {
+		match backend {
+			Backend::ChaCha20 => self.derive::<ChaCha20Rng>(),
+			_ => self.derive::<StdRng>(),
+		}
+	}
+
+	/// The same generator a seedable backend used for the roll with this sub-seed
+	pub fn replay(&self, backend: Backend) -> Generator {
+		match backend {
+			Backend::ChaCha20 => Generator::ChaCha20(self.generator()),
+			_ => Generator::Std(self.generator()),
+		}
+	}
+}
+
+impl Display for Seed {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Seed::Short(seed) => write!(f, "{}", seed),
+			Seed::Full(seed) => seed.iter().try_for_each(|byte| write!(f, "{:02x}", byte)),
+		}
+	}
+}
+
+impl FromStr for Seed {
+	type Err = String;
+
+	fn from_str(input: &str) -> Result<Self, Self::Err> {
+		let input = input.trim();
+		if let Ok(seed) = input.parse::<u64>() {
+			return Ok(Seed::Short(seed));
+		}
+		if input.len() != 64 || !input.is_ascii() {
+			return Err("Ein Seed ist eine Zahl oder 64 Hex Ziffern".to_string());
+		}
+		let mut seed = [0u8; 32];
+		for (index, byte) in seed.iter_mut().enumerate() {
+			*byte = u8::from_str_radix(&input[index * 2..index * 2 + 2], 16)
+				.map_err(|_| format!("Ungültige Hex Ziffer in {}", input))?;
+		}
+		Ok(Seed::Full(seed))
+	}
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+enum Instruction {
+	Rdseed,
+	Rdrand,
+}
+
+impl Display for Instruction {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Instruction::Rdseed => write!(f, "RDSEED"),
+			Instruction::Rdrand => write!(f, "RDRAND"),
+		}
+	}
+}
+
+impl Instruction {
+	fn supported(&self) -> bool {
+		#[cfg(target_arch = "x86_64")]
+		return match self {
+			Instruction::Rdseed => is_x86_feature_detected!("rdseed"),
+			Instruction::Rdrand => is_x86_feature_detected!("rdrand"),
+		};
+		#[cfg(not(target_arch = "x86_64"))]
+		false
+	}
+
+	/// Runs the instruction until it reports success. 0 and all bits set are rejected as well,
+	/// some AMD processors return them with success when the generator is broken
+	fn step(&self) -> Option<u64> {
+		#[cfg(target_arch = "x86_64")]
+		if self.supported() {
+			let mut value = 0u64;
+			for _ in 0..HARDWARE_RETRIES {
+				// Safety: the instruction is supported, checked above
+				let success = unsafe {
+					match self {
+						Instruction::Rdseed => _rdseed64_step(&mut value),
+						Instruction::Rdrand => _rdrand64_step(&mut value),
+					}
+				};
+				if success == 1 && value != 0 && value != u64::MAX {
+					return Some(value);
+				}
+				std::hint::spin_loop();
+			}
+		}
+		None
+	}
+}
+
+/**
+Generator using RDSEED or RDRAND, switches to a StdRng from the operating system if the instruction stops working
+ */
+pub struct HardwareRng {
+	instruction: Instruction,
+	fallback: Option<StdRng>,
+}
+
+impl HardwareRng {
+	/// Prefers RDSEED, uses RDRAND if RDSEED is not available or does not deliver
+	fn detect() -> Option<Self> {
+		[Instruction::Rdseed, Instruction::Rdrand]
+			.into_iter()
+			.find(|instruction| instruction.step().is_some())
+			.map(|instruction| HardwareRng { instruction, fallback: None })
+	}
+}
+
+impl RngCore for HardwareRng {
+	fn next_u32(&mut self) -> u32 {
+		self.next_u64() as u32
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		if self.fallback.is_none() {
+			if let Some(value) = self.instruction.step() {
+				return value;
+			}
+			edbgprintln!("{} liefert keine Zufallszahlen mehr, verwende StdRng", self.instruction);
+			self.fallback = Some(StdRng::seed_from_u64(os_seed().unwrap_or_else(time_seed)));
+		}
+		self.fallback.as_mut().map_or(0, RngCore::next_u64)
+	}
+
+	fn fill_bytes(&mut self, dst: &mut [u8]) {
+		for chunk in dst.chunks_mut(8) {
+			let bytes = self.next_u64().to_le_bytes();
+			chunk.copy_from_slice(&bytes[..chunk.len()]);
+		}
+	}
+}
+
+/**
+Generator using the operating system, switches to a StdRng if the operating system fails
+ */
+pub struct OsGenerator {
+	fallback: Option<StdRng>,
+}
+
+impl OsGenerator {
+	fn fallback(&mut self, err: impl Display) -> &mut StdRng {
+		self.fallback.get_or_insert_with(|| {
+			edbgprintln!("Zufall des Betriebssystems nicht verfügbar: {}, verwende StdRng", err);
+			StdRng::seed_from_u64(time_seed())
+		})
+	}
+}
+
+impl RngCore for OsGenerator {
+	fn next_u32(&mut self) -> u32 {
+		self.next_u64() as u32
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		if self.fallback.is_none() {
+			match OsRng.try_next_u64() {
+				Ok(value) => return value,
+				Err(err) => return self.fallback(err).next_u64(),
+			}
+		}
+		self.fallback.as_mut().map_or(0, RngCore::next_u64)
+	}
+
+	fn fill_bytes(&mut self, dst: &mut [u8]) {
+		if self.fallback.is_none() {
+			match OsRng.try_fill_bytes(dst) {
+				Ok(()) => return,
+				Err(err) => return self.fallback(err).fill_bytes(dst),
+			}
+		}
+		if let Some(fallback) = self.fallback.as_mut() {
+			fallback.fill_bytes(dst);
+		}
+	}
+}
+
+/**
+The generator used for one roll
+ */
+pub enum Generator {
+	Std(StdRng),
+	ChaCha20(ChaCha20Rng),
+	Os(OsGenerator),
+	Hardware(HardwareRng),
+}
+
+impl RngCore for Generator {
+	fn next_u32(&mut self) -> u32 {
+		match self {
+			Generator::Std(rng) => rng.next_u32(),
+			Generator::ChaCha20(rng) => rng.next_u32(),
+			Generator::Os(rng) => rng.next_u32(),
+			Generator::Hardware(rng) => rng.next_u32(),
+		}
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		match self {
+			Generator::Std(rng) => rng.next_u64(),
+			Generator::ChaCha20(rng) => rng.next_u64(),
+			Generator::Os(rng) => rng.next_u64(),
+			Generator::Hardware(rng) => rng.next_u64(),
+		}
+	}
+
+	fn fill_bytes(&mut self, dst: &mut [u8]) {
+		match self {
+			Generator::Std(rng) => rng.fill_bytes(dst),
+			Generator::ChaCha20(rng) => rng.fill_bytes(dst),
+			Generator::Os(rng) => rng.fill_bytes(dst),
+			Generator::Hardware(rng) => rng.fill_bytes(dst),
+		}
+	}
+}
+
+fn os_seed() -> Option<u64> {
+	OsRng.try_next_u64().ok()
+}
+
+fn time_seed() -> u64 {
+	// C style initialization
+	SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos() as u64
+}
+
+/**
+A seed from the hardware random generator, the operating system or the current time, whichever works first
+ */
+fn entropy_seed(full: bool) -> Seed {
+	let mut hardware = HardwareRng::detect();
+	let mut next = || {
+		hardware.as_mut()
+			.map(RngCore::next_u64)
+			.or_else(os_seed)
+			.unwrap_or_else(time_seed)
+	};
+	if !full {
+		return Seed::Short(next());
+	}
+	let mut seed = [0u8; 32];
+	for chunk in seed.chunks_mut(8) {
+		chunk.copy_from_slice(&next().to_le_bytes());
+	}
+	Seed::Full(seed)
+}
+
+/**
+Hands out one generator per roll. For the seedable backends every roll gets its own sub-seed,
+the first one is the seed itself and every following one is derived from the previous one.
+So starting with a sub-seed as `--seed` replays that roll and everything after it
+ */
+pub struct Seeds {
+	backend: Backend,
+	seed: Option<Seed>,
+	next: Option<Seed>,
+	/// Sub-seed of the generator handed out last
+	last: Option<Seed>,
+	print: bool,
+	/// Hash of the seed in the audit mode, the seed itself is only shown by [`Seeds::reveal`]
+	commitment: Option<String>,
+	/// Why the requested backend is not used
+	notes: Vec<String>,
+}
+
+impl Seeds {
+	/// Sets up the requested backend, falling back along hardware -> os -> std if it is not available.
+	/// A fixed seed needs a seedable backend, so it falls back to std as well
+	pub fn new(requested: Backend, seed: Option<Seed>, print: bool) -> Self {
+		let mut notes = vec![];
+		let mut backend = requested;
+		if backend == Backend::Hardware && HardwareRng::detect().is_none() {
+			notes.push("RDSEED und RDRAND sind nicht verfügbar".to_string());
+			backend = Backend::Os;
+		}
+		if backend == Backend::Os && os_seed().is_none() {
+			notes.push("Der Zufall des Betriebssystems ist nicht verfügbar".to_string());
+			backend = Backend::Std;
+		}
+		if seed.is_some() && !backend.seedable() {
+			notes.push(format!("{} kann keinen Seed verwenden", backend));
+			backend = Backend::Std;
+		}
+
+		let seed = match backend.seedable() {
+			true => Some(seed.unwrap_or_else(|| entropy_seed(backend == Backend::ChaCha20))),
+			false => None,
+		};
+		Seeds {
+			backend,
+			seed,
+			next: seed,
+			last: None,
+			print,
+			commitment: None,
+			notes,
+		}
+	}
+
+	/// Audit mode, the seed is kept secret until the end and only its commitment is shown.
+	/// Always uses chacha20, it is the same on every platform and version.
+	/// A given seed has to be a full one, a number could be found from the commitment by trying
+	pub fn audited(requested: Backend, seed: Option<Seed>) -> Result<Self, String> {
+		if let Some(Seed::Short(_)) = seed {
+			return Err("Im Audit Modus muss der Seed aus 64 Hex Ziffern bestehen, eine Zahl ist zu leicht zu erraten".to_string());
+		}
+		let mut seeds = Seeds::new(audit::BACKEND, seed, false);
+		if requested != audit::BACKEND {
+			seeds.notes.push(format!("Im Audit Modus wird {} statt {} verwendet", audit::BACKEND, requested));
+		}
+		seeds.commitment = seeds.seed.map(|seed| audit::commitment(&seed));
+		// The sub-seeds are written to the history, so the chain starts after the secret seed
+		seeds.next = seeds.seed.map(|seed| seed.next(audit::BACKEND));
+		Ok(seeds)
+	}
+
+	pub fn commitment(&self) -> Option<&str> {
+		self.commitment.as_deref()
+	}
+
+	/// The seed of the audit mode, with which `würfeln verify` checks the rolls
+	pub fn reveal(&self) -> Option<String> {
+		let seed = self.seed.filter(|_| self.commitment.is_some())?;
+		Some(format!("Audit: Seed {}\nPrüfen mit: würfeln verify {} [Verlauf]", seed, seed))
+	}
+
+	/// Which backend is used, with the seed and the reasons for a fallback
+	pub fn report(&self) -> String {
+		let mut report = format!("Zufallsgenerator: {}", self.backend);
+		if let Some(hardware) = HardwareRng::detect().filter(|_| self.backend == Backend::Hardware) {
+			report += &format!(" ({})", hardware.instruction);
+		}
+		match (&self.commitment, self.seed) {
+			(Some(commitment), _) => report += &format!("\nAudit: Commitment {}", commitment),
+			(None, Some(seed)) => report += &format!("\nSeed: {}", seed),
+			(None, None) => {},
+		}
+		for note in &self.notes {
+			report += &format!("\n{}", note);
+		}
+		report
+	}
+
+	fn generator(&self) -> Generator {
+		match (self.backend, self.next) {
+			(Backend::ChaCha20, Some(seed)) => Generator::ChaCha20(seed.generator()),
+			(Backend::Os, _) => Generator::Os(OsGenerator { fallback: None }),
+			(Backend::Hardware, _) => match HardwareRng::detect() {
+				Some(hardware) => Generator::Hardware(hardware),
+				None => Generator::Os(OsGenerator { fallback: None }),
+			},
+			(_, seed) => Generator::Std(seed.unwrap_or(Seed::Short(time_seed())).generator()),
+		}
+	}
+
+	/// Sub-seed which replays the last roll, `None` for the backends without seed
+	pub fn last(&self) -> Option<Seed> {
+		self.last
+	}
+
+	/// Generator for the next roll. Its sub-seed stays the next one until [`Seeds::advance`],
+	/// so a roll which is not recorded does not leave a gap in the chain
+	pub fn next_rng(&mut self) -> Generator {
+		let generator = self.generator();
+		self.last = self.next;
+		if let Some(sub_seed) = self.next.filter(|_| self.print) {
+			edbgprintln!("Sub-Seed: {}", sub_seed);
+		}
+		generator
+	}
+
+	/// Moves on to the next sub-seed, after a roll with the current one was recorded
+	pub fn advance(&mut self) {
+		self.next = self.next.map(|sub_seed| sub_seed.next(self.backend));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::rng::{Backend, Seed, Seeds};
+	use rand::Rng;
+
+	#[test]
+	fn test_replay_sub_seed() {
+		for seed in ["42", "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f"] {
+			for backend in [Backend::Std, Backend::ChaCha20] {
+				let seed: Seed = seed.parse().unwrap();
+				let mut seeds = Seeds::new(backend, Some(seed), false);
+				let first: u64 = seeds.next_rng().random();
+				// Not recorded, the next roll uses the same sub-seed
+				assert_eq!(seeds.next_rng().random::<u64>(), first);
+				seeds.advance();
+				let sub_seed = seeds.next;
+				let second: Vec<u64> = (0..3).map(|_| seeds.next_rng().random()).collect();
+
+				assert_eq!(Seeds::new(backend, Some(seed), false).next_rng().random::<u64>(), first);
+				let mut replay = Seeds::new(backend, sub_seed, false);
+				let replayed: Vec<u64> = (0..3).map(|_| replay.next_rng().random()).collect();
+				assert_eq!(replayed, second);
+			}
+		}
+	}
+
+	#[test]
+	fn test_parse_seed() {
+		assert_eq!("7".parse::<Seed>(), Ok(Seed::Short(7)));
+		let full = "ff".repeat(32);
+		assert_eq!(full.parse::<Seed>(), Ok(Seed::Full([255; 32])));
+		assert_eq!(full.parse::<Seed>().unwrap().to_string(), full);
+		assert!("abc".parse::<Seed>().is_err());
+		assert!("zz".repeat(32).parse::<Seed>().is_err());
+	}
+
+	#[test]
+	fn test_seed_needs_seedable_backend() {
+		let seeds = Seeds::new(Backend::Os, Some(Seed::Short(1)), false);
+		assert_eq!(seeds.backend, Backend::Std);
+		assert!(!seeds.notes.is_empty());
+	}
+
+	#[test]
+	fn test_audit_keeps_seed_secret() {
+		let seed: Seed = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f".parse().unwrap();
+		let mut seeds = Seeds::audited(Backend::Std, Some(seed)).unwrap();
+		for _ in 0..5 {
+			seeds.next_rng();
+			seeds.advance();
+			assert!(seeds.last.is_some_and(|sub_seed| sub_seed != seed));
+		}
+		assert!(Seeds::audited(Backend::ChaCha20, Some(Seed::Short(42))).is_err());
+		assert!(Seeds::audited(Backend::ChaCha20, None).unwrap().commitment().is_some());
+	}
+}
@@ -1,14 +1,152 @@
 use crate::common::{settings_path, Loadable, Rollable};
 use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
 use std::fs::{File, OpenOptions};
 use std::io::{BufReader, BufWriter};
 use std::path::Path;
-use rand::distr::Uniform;
+use rand::distr::weighted::WeightedIndex;
 use rand::Rng;
 
+/**
+A single spell. In `spell.yaml` a spell without metadata can be written as just its name
+ */
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, Hash, Debug)]
+#[serde(from = "RawSpell", into = "RawSpell")]
+pub struct Spell {
+	pub name: String,
+	pub description: String,
+	pub mana: Option<u32>,
+	pub range: Option<String>,
+	/// Relative chance to be rolled, 0 means the spell is never rolled
+	pub weight: u32,
+	pub level: Option<u32>,
+}
+
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Hash, Debug)]
+#[serde(untagged)]
+pub enum RawSpell {
+	Name(String),
+	Detailed {
+		name: String,
+		#[serde(default, skip_serializing_if = "String::is_empty")]
+		description: String,
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		mana: Option<u32>,
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		range: Option<String>,
+		#[serde(default = "default_weight")]
+		weight: u32,
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		level: Option<u32>,
+	},
+}
+
+fn default_weight() -> u32 {
+	1
+}
+
+impl From<RawSpell> for Spell {
+	fn from(raw: RawSpell) -> Self {
+		match raw {
+			RawSpell::Name(name) => Spell::from(name),
+			RawSpell::Detailed { name, description, mana, range, weight, level } => Spell {
+				name,
+				description,
+				mana,
+				range,
+				weight,
+				level,
+			},
+		}
+	}
+}
+
+impl From<Spell> for RawSpell {
+	fn from(spell: Spell) -> Self {
+		if spell.description.is_empty() && spell.mana.is_none() && spell.range.is_none() && spell.weight == 1 && spell.level.is_none() {
+			RawSpell::Name(spell.name)
+		} else {
+			RawSpell::Detailed {
+				name: spell.name,
+				description: spell.description,
+				mana: spell.mana,
+				range: spell.range,
+				weight: spell.weight,
+				level: spell.level,
+			}
+		}
+	}
+}
+
+impl From<String> for Spell {
+	fn from(name: String) -> Self {
+		Spell {
+			name,
+			description: String::new(),
+			mana: None,
+			range: None,
+			weight: default_weight(),
+			level: None,
+		}
+	}
+}
+
+impl Display for Spell {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.name)?;
+		let mut details: Vec<String> = Vec::with_capacity(3);
+		if let Some(mana) = self.mana {
+			details.push(format!("{} Mana", mana));
+		}
+		if let Some(range) = &self.range {
+			details.push(format!("Reichweite: {}", range));
+		}
+		if let Some(level) = self.level {
+			details.push(format!("ab Level {}", level));
+		}
+		if !details.is_empty() {
+			write!(f, " ({})", details.join(", "))?;
+		}
+		if !self.description.is_empty() {
+			write!(f, "\n{}", self.description)?;
+		}
+		Ok(())
+	}
+}
+
+/**
+Machine readable spell, unlike in `spell.yaml` always with every field
+ */
+#[derive(Debug, Serialize)]
+pub struct DrawnSpell {
+	pub category: String,
+	pub name: String,
+	pub description: String,
+	pub mana: Option<u32>,
+	pub range: Option<String>,
+	pub level: Option<u32>,
+}
+
+impl DrawnSpell {
+	pub fn new(category: &str, spell: &Spell) -> Self {
+		DrawnSpell {
+			category: category.to_string(),
+			name: spell.name.clone(),
+			description: spell.description.clone(),
+			mana: spell.mana,
+			range: spell.range.clone(),
+			level: spell.level,
+		}
+	}
+}
+
+fn named(spells: Vec<String>) -> Vec<Spell> {
+	spells.into_iter().map(Spell::from).collect()
+}
+
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, Hash, Debug)]
 pub struct Spells {
-	pub spells: Vec<String>,
+	pub spells: Vec<Spell>,
 	pub name: String,
 }
 
@@ -17,7 +155,7 @@ impl Spells {
 		vec![
 			Spells {
 				name: "Projektionszauber".to_string(),
-				spells: vec![
+				spells: named(vec![
 					"Astrale Ohren".to_string(),
 					"Astrale Projektion".to_string(),
 					"Astrales Rauschen".to_string(),
@@ -32,11 +170,11 @@ impl Spells {
 					"Visionär".to_string(),
 					"Wetterkontrolle".to_string(),
 					"Zeichnen".to_string(),
-				],
+				]),
 			},
 			Spells {
 				name: "Heil- und Schutzzauber".to_string(),
-				spells: vec![
+				spells: named(vec![
 					"Attributsschub".to_string(),
 					"Desinfektor".to_string(),
 					"Elementarschild".to_string(),
@@ -47,21 +185,21 @@ impl Spells {
 					"Manablockade".to_string(),
 					"Projektilschild".to_string(),
 					"Reparator".to_string(),
-				],
+				]),
 			},
 			Spells {
 				name: "Geistermagie".to_string(),
-				spells: vec![
+				spells: named(vec![
 					"Binden".to_string(),
 					"Heilige Erlösung".to_string(),
 					"Segnen".to_string(),
 					"Späher".to_string(),
 					"Verbannen".to_string(),
-				],
+				]),
 			},
 			Spells {
 				name: "Manipulationszauber".to_string(),
-				spells: vec![
+				spells: named(vec![
 					"Brecher".to_string(),
 					"Feger".to_string(),
 					"Freie Zunge".to_string(),
@@ -75,11 +213,11 @@ impl Spells {
 					"Statusbrecher".to_string(),
 					"Vergrößern".to_string(),
 					"Zeitkontrolle".to_string(),
-				],
+				]),
 			},
 			Spells {
 				name: "Kampfzauber".to_string(),
-				spells: vec![
+				spells: named(vec![
 					"Dampfgarer".to_string(),
 					"Elementar-Emitter".to_string(),
 					"Energieball".to_string(),
@@ -97,7 +235,7 @@ impl Spells {
 					"Toxin".to_string(),
 					"Trance".to_string(),
 					"Windhose".to_string(),
-				],
+				]),
 			},
 		]
 	}
@@ -136,9 +274,198 @@ impl Loadable<Vec<Spells>> for Spells {
 	}
 }
 
-impl Rollable<String> for Spells  {
-    fn roll(&self, rng: &mut impl Rng) -> String {
-		let distribution = Uniform::new_inclusive(0, self.spells.len() - 1).expect("Failed to create distribution for Spells");
-        self.spells[rng.sample(distribution)].clone()
+impl Rollable<Option<Spell>> for Spells  {
+	/// Picks a spell according to the weights, none if no spell has a weight above 0
+    fn roll(&self, rng: &mut impl Rng) -> Option<Spell> {
+		let distribution = WeightedIndex::new(self.spells.iter().map(|spell| spell.weight)).ok()?;
+        Some(self.spells[rng.sample(distribution)].clone())
     }
-}
\ No newline at end of file
+}
+
+/**
+Draws up to `amount` distinct spells from all given categories without replacement.
+Every spell is weighted by its own weight, so bigger categories are drawn more often.
+Spells with weight 0 are never drawn, so fewer spells may be returned.
+Returns the name of the category together with each spell
+ */
+pub fn draw<'a>(categories: &[&'a Spells], amount: usize, rng: &mut impl Rng) -> Vec<(&'a str, Spell)> {
+	let mut pool: Vec<(&'a str, &'a Spell)> = categories.iter()
+		.flat_map(|category| category.spells.iter().map(move |spell| (&*category.name, spell)))
+		.collect();
+	let mut drawn = Vec::with_capacity(amount.min(pool.len()));
+	while drawn.len() < amount && !pool.is_empty() {
+		// Only spells with weight 0 are left
+		let Ok(distribution) = WeightedIndex::new(pool.iter().map(|(_, spell)| spell.weight)) else {
+			break;
+		};
+		let (category, spell) = pool.swap_remove(rng.sample(distribution));
+		drawn.push((category, spell.clone()));
+	}
+	drawn
+}
+
+/**
+Finds a category by its exact name, ignoring case or by an unambiguous prefix
+ */
+pub fn find<'a>(spells: &'a [Spells], name: &str) -> Option<&'a Spells> {
+	let name = name.trim();
+	if name.is_empty() {
+		return None;
+	}
+	let lowercase = name.to_lowercase();
+	let mut prefixed = spells.iter().filter(|category| category.name.to_lowercase().starts_with(&lowercase));
+	spells.iter()
+		.find(|category| category.name == name)
+		.or_else(|| spells.iter().find(|category| category.name.to_lowercase() == lowercase))
+		.or_else(|| match (prefixed.next(), prefixed.next()) {
+			(Some(category), None) => Some(category),
+			_ => None,
+		})
+}
+
+/**
+Restricts which spells are shown in the spell catalogue.
+Spells without the filtered metadata are always shown
+ */
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct SpellFilter {
+	/// Empty means all categories
+	pub categories: Vec<String>,
+	pub max_mana: Option<u32>,
+	pub level: Option<u32>,
+	pub range: Option<String>,
+}
+
+impl SpellFilter {
+	pub fn is_empty(&self) -> bool {
+		self == &SpellFilter::default()
+	}
+
+	pub fn matches(&self, category: &str, spell: &Spell) -> bool {
+		(self.categories.is_empty() || self.categories.iter().any(|name| name == category))
+			&& self.max_mana.is_none_or(|max| spell.mana.is_none_or(|mana| mana <= max))
+			&& self.level.is_none_or(|level| spell.level.is_none_or(|minimum| minimum <= level))
+			&& self.range.as_ref().is_none_or(|range| {
+				spell.range.as_ref().is_none_or(|spell_range| spell_range.to_lowercase().contains(&range.to_lowercase()))
+			})
+	}
+
+	/// All spells matching the filter together with their category
+	pub fn apply<'a>(&self, spells: &'a [Spells]) -> Vec<(&'a str, &'a Spell)> {
+		spells.iter()
+			.flat_map(|category| category.spells.iter().map(move |spell| (&*category.name, spell)))
+			.filter(|(category, spell)| self.matches(category, spell))
+			.collect()
+	}
+}
+
+impl Display for SpellFilter {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		if self.is_empty() {
+			return write!(f, "Kein Filter");
+		}
+		let mut parts: Vec<String> = Vec::with_capacity(4);
+		if !self.categories.is_empty() {
+			parts.push(format!("Kategorien: {}", self.categories.join(", ")));
+		}
+		if let Some(mana) = self.max_mana {
+			parts.push(format!("Maximal {} Mana", mana));
+		}
+		if let Some(level) = self.level {
+			parts.push(format!("Level {}", level));
+		}
+		if let Some(range) = &self.range {
+			parts.push(format!("Reichweite: {}", range));
+		}
+		write!(f, "{}", parts.join(", "))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::common::Rollable;
+	use crate::spell::{draw, find, Spell, Spells};
+	use rand::rngs::StdRng;
+	use rand::SeedableRng;
+
+	#[test]
+	fn test_mixed_yaml() {
+		let yaml = "
+- name: Kampfzauber
+  spells:
+    - Flamme
+    - name: Inferno
+      description: Sehr heiß
+      mana: 5
+      weight: 0
+";
+		let spells: Vec<Spells> = serde_yaml::from_str(yaml).unwrap();
+		assert_eq!(spells[0].spells[0], Spell::from(String::from("Flamme")));
+		assert_eq!(spells[0].spells[1].mana, Some(5));
+		assert_eq!(spells[0].spells[1].weight, 0);
+
+		let written = serde_yaml::to_string(&spells).unwrap();
+		assert!(written.contains("- Flamme"));
+		assert_eq!(serde_yaml::from_str::<Vec<Spells>>(&written).unwrap(), spells);
+	}
+
+	#[test]
+	fn test_weighted_roll() {
+		let mut never = Spell::from(String::from("Inferno"));
+		never.weight = 0;
+		let spells = Spells {
+			name: String::from("Kampfzauber"),
+			spells: vec![never, Spell::from(String::from("Flamme"))],
+		};
+		let mut rng = StdRng::seed_from_u64(0);
+		assert!((0..100).all(|_| spells.roll(&mut rng).unwrap().name == "Flamme"));
+
+		let disabled = Spells { spells: vec![spells.spells[0].clone()], ..spells.clone() };
+		assert_eq!(disabled.roll(&mut rng), None);
+		let empty = Spells { spells: vec![], ..spells };
+		assert_eq!(empty.roll(&mut rng), None);
+	}
+
+	#[test]
+	fn test_find_category() {
+		let spells = Spells::defaults();
+		assert_eq!(find(&spells, "Kampfzauber").unwrap().name, "Kampfzauber");
+		assert_eq!(find(&spells, "kampf").unwrap().name, "Kampfzauber");
+		assert!(find(&spells, "").is_none());
+		assert!(find(&spells, "Drachen").is_none());
+	}
+
+	#[test]
+	fn test_draw_distinct() {
+		let first = Spells {
+			name: String::from("Kampfzauber"),
+			spells: vec![Spell::from(String::from("Flamme")), Spell::from(String::from("Inferno"))],
+		};
+		let second = Spells {
+			name: String::from("Geistermagie"),
+			spells: vec![Spell::from(String::from("Binden"))],
+		};
+		let mut rng = StdRng::seed_from_u64(0);
+		let mut drawn = draw(&[&first, &second], 5, &mut rng);
+		assert_eq!(drawn.len(), 3);
+		drawn.sort();
+		assert_eq!(drawn[0], ("Geistermagie", Spell::from(String::from("Binden"))));
+		assert_eq!(drawn[1].1.name, "Flamme");
+		assert_eq!(drawn[2].1.name, "Inferno");
+	}
+
+	#[test]
+	fn test_draw_skips_weight_zero() {
+		let mut never = Spell::from(String::from("Inferno"));
+		never.weight = 0;
+		let spells = Spells {
+			name: String::from("Kampfzauber"),
+			spells: vec![never, Spell::from(String::from("Flamme"))],
+		};
+		let mut rng = StdRng::seed_from_u64(0);
+		for _ in 0..20 {
+			let drawn = draw(&[&spells], 2, &mut rng);
+			assert_eq!(drawn, vec![("Kampfzauber", Spell::from(String::from("Flamme")))]);
+		}
+	}
+}
@@ -0,0 +1,312 @@
+use crate::dice::colored_dice::ColoredDices;
+use crate::dice::normal_dice::calculate_variance;
+use crate::history::{Entry, Kind};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+/// Chance of a 5 or 6
+const SUCCESS_CHANCE: f64 = 2.0 / 6.0;
+/// Chance of a 1
+const FAILURE_CHANCE: f64 = 1.0 / 6.0;
+
+/// The part of a recorded normal roll the statistics need
+#[derive(Deserialize)]
+struct RecordedRoll {
+	sides: u8,
+	counts: Vec<u64>,
+}
+
+#[derive(Deserialize)]
+struct RecordedColored {
+	dice: Vec<RecordedDice>,
+}
+
+#[derive(Deserialize)]
+struct RecordedDice {
+	short: char,
+	/// Missing in old entries, they can not be compared to the expectation
+	#[serde(default)]
+	amount: usize,
+	result: u64,
+}
+
+/**
+Observed sum of independent dice against the sum they are expected to have
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize)]
+pub struct Deviation {
+	pub observed: f64,
+	pub expected: f64,
+	pub variance: f64,
+}
+
+impl Deviation {
+	fn add(&mut self, observed: f64, expected: f64, variance: f64) {
+		self.observed += observed;
+		self.expected += expected;
+		self.variance += variance;
+	}
+
+	/// Standard deviations above the expectation, `None` if nothing random was rolled
+	pub fn z_score(&self) -> Option<f64> {
+		(self.variance > 0.0).then(|| (self.observed - self.expected) / self.variance.sqrt())
+	}
+}
+
+/**
+Every W6 of the normal dice together
+ */
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct D6Statistics {
+	pub dice: u64,
+	/// How often each face was rolled, the first entry counts the ones
+	pub counts: Vec<u64>,
+	/// How often each face is rolled by a fair die
+	pub expected_count: f64,
+	/// Of the counts, see [`calculate_variance`]
+	pub variance: f64,
+	pub expected_variance: f64,
+	/// Share of 5 and 6
+	pub success_rate: f64,
+	/// Share of 1
+	pub failure_rate: f64,
+	pub successes: Deviation,
+}
+
+impl D6Statistics {
+	fn new(counts: Vec<u64>) -> Option<Self> {
+		let dice: u64 = counts.iter().sum();
+		if dice == 0 {
+			return None;
+		}
+		let successes = (counts[4] + counts[5]) as f64;
+		Some(D6Statistics {
+			dice,
+			expected_count: dice as f64 / 6.0,
+			variance: calculate_variance(&counts, dice as u32, 6),
+			// Every count is binomial distributed
+			expected_variance: dice as f64 * (1.0 / 6.0) * (5.0 / 6.0),
+			success_rate: successes / dice as f64,
+			failure_rate: counts[0] as f64 / dice as f64,
+			successes: Deviation {
+				observed: successes,
+				expected: dice as f64 * SUCCESS_CHANCE,
+				variance: dice as f64 * SUCCESS_CHANCE * (1.0 - SUCCESS_CHANCE),
+			},
+			counts,
+		})
+	}
+}
+
+/**
+Totals of the colored dice against the average of their faces
+ */
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct ColoredStatistics {
+	pub rolls: usize,
+	/// Average total per roll
+	pub average: f64,
+	pub expected_average: f64,
+	pub total: Deviation,
+}
+
+/**
+Statistics of a part of the history, e.g. a session or a player
+ */
+#[derive(Clone, PartialEq, Debug, Default, Serialize)]
+pub struct Statistics {
+	pub rolls: usize,
+	pub kinds: BTreeMap<Kind, usize>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub d6: Option<D6Statistics>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub colored: Option<ColoredStatistics>,
+	/// Combined z-score of the W6 successes and the colored totals, above 0 is luckier than expected
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub luck: Option<f64>,
+}
+
+impl Statistics {
+	pub fn new(entries: &[&Entry], colored_dice: &ColoredDices) -> Self {
+		let mut kinds = BTreeMap::new();
+		let mut counts = vec![0u64; 6];
+		let mut colored_rolls = 0;
+		let mut colored = Deviation::default();
+		for entry in entries {
+			*kinds.entry(entry.kind).or_insert(0) += 1;
+			match entry.kind {
+				Kind::Roll => {
+					let rolls = serde_json::from_value::<Vec<RecordedRoll>>(entry.result.clone()).unwrap_or_default();
+					for roll in rolls.iter().filter(|roll| roll.sides == 6 && roll.counts.len() == 6) {
+						counts.iter_mut().zip(&roll.counts).for_each(|(total, count)| *total += count);
+					}
+				},
+				Kind::Colored => {
+					let Ok(roll) = serde_json::from_value::<RecordedColored>(entry.result.clone()) else {
+						continue;
+					};
+					let known: Vec<_> = roll.dice.iter()
+						.filter(|dice| dice.amount > 0)
+						.filter_map(|dice| colored_dice.find(dice.short).map(|known| (known, dice)))
+						.collect();
+					if known.is_empty() {
+						continue;
+					}
+					colored_rolls += 1;
+					for (known, dice) in known {
+						colored.add(dice.result as f64, known.mean() * dice.amount as f64, known.variance() * dice.amount as f64);
+					}
+				},
+				_ => {},
+			}
+		}
+
+		let d6 = D6Statistics::new(counts);
+		let colored = (colored_rolls > 0).then(|| ColoredStatistics {
+			rolls: colored_rolls,
+			average: colored.observed / colored_rolls as f64,
+			expected_average: colored.expected / colored_rolls as f64,
+			total: colored,
+		});
+		// Stouffer's method, every z-score weighs the same
+		let scores: Vec<f64> = [
+			d6.as_ref().and_then(|d6| d6.successes.z_score()),
+			colored.as_ref().and_then(|colored| colored.total.z_score()),
+		].into_iter().flatten().collect();
+		let luck = (!scores.is_empty()).then(|| scores.iter().sum::<f64>() / (scores.len() as f64).sqrt());
+		Statistics {
+			rolls: entries.len(),
+			kinds,
+			d6,
+			colored,
+			luck,
+		}
+	}
+}
+
+impl Display for Statistics {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		let kinds: Vec<String> = self.kinds.iter().map(|(kind, count)| format!("{}: {}", kind, count)).collect();
+		write!(f, "Würfe: {}", self.rolls)?;
+		if !kinds.is_empty() {
+			write!(f, " ({})", kinds.join(", "))?;
+		}
+		if let Some(d6) = &self.d6 {
+			let counts: Vec<String> = d6.counts.iter().enumerate().map(|(face, count)| format!("{}: {}", face + 1, count)).collect();
+			write!(f, "\nW6: {} Würfel, erwartet {:.1} je Augenzahl", d6.dice, d6.expected_count)?;
+			write!(f, "\n{}", counts.join(", "))?;
+			write!(f, "\nVarianz: {:.2} (erwartet {:.2})", d6.variance, d6.expected_variance)?;
+			write!(f, "\nErfolge: {:.1} % (erwartet {:.1} %)", d6.success_rate * 100.0, SUCCESS_CHANCE * 100.0)?;
+			write!(f, "\nMisserfolge: {:.1} % (erwartet {:.1} %)", d6.failure_rate * 100.0, FAILURE_CHANCE * 100.0)?;
+		}
+		if let Some(colored) = &self.colored {
+			write!(
+				f,
+				"\nFarbige Würfel: {} Würfe, Durchschnitt {:.2} (erwartet {:.2})",
+				colored.rolls,
+				colored.average,
+				colored.expected_average
+			)?;
+		}
+		if let Some(luck) = self.luck {
+			write!(f, "\nGlück: {:+.2} Standardabweichungen", luck)?;
+		}
+		Ok(())
+	}
+}
+
+/**
+Statistics of all given rolls, of every player and who of them was the luckiest
+ */
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct Report {
+	pub all: Statistics,
+	pub players: BTreeMap<String, Statistics>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub luckiest: Option<String>,
+}
+
+impl Report {
+	pub fn new(entries: &[&Entry], colored_dice: &ColoredDices) -> Self {
+		let mut by_player: BTreeMap<&str, Vec<&Entry>> = BTreeMap::new();
+		for entry in entries {
+			if let Some(player) = &entry.player {
+				by_player.entry(player.as_str()).or_default().push(entry);
+			}
+		}
+		let players: BTreeMap<String, Statistics> = by_player.into_iter()
+			.map(|(player, entries)| (player.to_string(), Statistics::new(&entries, colored_dice)))
+			.collect();
+		let luckiest = players.iter()
+			.filter_map(|(player, statistics)| statistics.luck.map(|luck| (player, luck)))
+			.max_by(|a, b| a.1.total_cmp(&b.1))
+			.map(|(player, _)| player.clone());
+		Report {
+			all: Statistics::new(entries, colored_dice),
+			players,
+			luckiest,
+		}
+	}
+}
+
+impl Display for Report {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.all)?;
+		for (player, statistics) in &self.players {
+			write!(f, "\n\nSpieler {}:\n{}", player, statistics)?;
+		}
+		if let Some(player) = &self.luckiest {
+			let luck = self.players.get(player).and_then(|statistics| statistics.luck).unwrap_or_default();
+			write!(f, "\n\nGlückspilz: {} ({:+.2})", player, luck)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::dice::colored_dice::ColoredDices;
+	use crate::history::{Entry, Kind};
+	use crate::statistics::Report;
+	use serde_json::json;
+
+	fn entry(kind: Kind, player: &str, result: serde_json::Value) -> Entry {
+		Entry {
+			timestamp: 0,
+			kind,
+			input: String::new(),
+			result,
+			text: String::new(),
+			seed: None,
+			player: Some(player.to_string()),
+			physical: false,
+			session: None,
+			commitment: None,
+		}
+	}
+
+	#[test]
+	fn test_report() {
+		let entries = [
+			entry(Kind::Roll, "Anna", json!([{ "sides": 6, "counts": [0, 0, 0, 0, 3, 3] }, { "sides": 20, "counts": [1] }])),
+			entry(Kind::Roll, "Bernd", json!([{ "sides": 6, "counts": [3, 1, 1, 1, 0, 0] }])),
+			entry(Kind::Colored, "Bernd", json!({ "dice": [{ "short": "r", "amount": 3, "result": 0 }] })),
+			entry(Kind::Spell, "Bernd", json!([])),
+		];
+		let entries: Vec<&Entry> = entries.iter().collect();
+		let report = Report::new(&entries, &ColoredDices::default());
+
+		let d6 = report.all.d6.as_ref().unwrap();
+		assert_eq!(report.all.rolls, 4);
+		assert_eq!(d6.dice, 12);
+		assert_eq!(d6.counts, vec![3, 1, 1, 1, 3, 3]);
+		assert_eq!(d6.success_rate, 0.5);
+		assert_eq!(d6.failure_rate, 0.25);
+		let colored = report.players["Bernd"].colored.as_ref().unwrap();
+		assert_eq!(colored.rolls, 1);
+		assert!((colored.expected_average - 2.0).abs() < 1e-9);
+		assert!(report.players["Anna"].colored.is_none());
+		assert_eq!(report.luckiest.as_deref(), Some("Anna"));
+	}
+}
@@ -0,0 +1,369 @@
+use crate::common::settings_path;
+use crate::edbgprintln;
+use rand::distr::weighted::WeightedIndex;
+use rand::distr::Uniform;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::{create_dir_all, read_dir, File};
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+const TABLE_DIR: &str = "tables";
+
+/// Nested references deeper than this are treated as a loop
+const MAX_DEPTH: usize = 16;
+
+/**
+Row of a random table. In the YAML file a row without any options can be written as just its text
+ */
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(try_from = "RawRow", into = "RawRow")]
+pub struct Row {
+	pub text: String,
+	/// Relative chance to be rolled, only used if the table has no dice
+	pub weight: u32,
+	/// Dice results which select this row, only used if the table has dice
+	pub range: Option<(u32, u32)>,
+	/// Name of another table which is rolled on after this row
+	pub table: Option<String>,
+}
+
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+enum RawRange {
+	Single(u32),
+	Span(String),
+}
+
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+enum RawRow {
+	Text(String),
+	Detailed {
+		#[serde(default, skip_serializing_if = "String::is_empty")]
+		text: String,
+		#[serde(default = "default_weight", skip_serializing_if = "is_default_weight")]
+		weight: u32,
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		range: Option<RawRange>,
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		table: Option<String>,
+	},
+}
+
+fn default_weight() -> u32 {
+	1
+}
+
+fn is_default_weight(weight: &u32) -> bool {
+	*weight == default_weight()
+}
+
+/**
+Parses `4` or `1-3` into an inclusive range
+ */
+fn parse_range(range: RawRange) -> Result<(u32, u32), String> {
+	let span = match range {
+		RawRange::Single(value) => return Ok((value, value)),
+		RawRange::Span(span) => span,
+	};
+	let parts: Vec<&str> = span.split(['-', '–']).map(str::trim).collect();
+	let numbers: Result<Vec<u32>, _> = parts.iter().map(|part| part.parse::<u32>()).collect();
+	match numbers.as_deref() {
+		Ok([value]) => Ok((*value, *value)),
+		Ok([min, max]) if min <= max => Ok((*min, *max)),
+		_ => Err(format!("Ungültiger Bereich: {}", span)),
+	}
+}
+
+impl TryFrom<RawRow> for Row {
+	type Error = String;
+
+	fn try_from(raw: RawRow) -> Result<Self, Self::Error> {
+		match raw {
+			RawRow::Text(text) => Ok(Row {
+				text,
+				weight: default_weight(),
+				range: None,
+				table: None,
+			}),
+			RawRow::Detailed { text, weight, range, table } => {
+				if text.is_empty() && table.is_none() {
+					return Err("Eine Zeile braucht einen Text oder eine Tabelle".to_string());
+				}
+				Ok(Row {
+					text,
+					weight,
+					range: range.map(parse_range).transpose()?,
+					table,
+				})
+			},
+		}
+	}
+}
+
+impl From<Row> for RawRow {
+	fn from(row: Row) -> Self {
+		if row.weight == 1 && row.range.is_none() && row.table.is_none() {
+			return RawRow::Text(row.text);
+		}
+		RawRow::Detailed {
+			text: row.text,
+			weight: row.weight,
+			range: row.range.map(|(min, max)| {
+				if min == max {
+					RawRange::Single(min)
+				} else {
+					RawRange::Span(format!("{}-{}", min, max))
+				}
+			}),
+			table: row.table,
+		}
+	}
+}
+
+/**
+A random table from the `tables` folder, every file contains one table
+ */
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Table {
+	/// Defaults to the file name
+	#[serde(default)]
+	pub name: String,
+	/// Number of sides of the dice which selects a row by its range. Without dice rows are chosen by weight
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub dice: Option<u32>,
+	pub rows: Vec<Row>,
+}
+
+/**
+One row which was rolled, nested tables produce one result each
+ */
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub struct TableResult {
+	pub table: String,
+	/// The dice result, if the table has dice
+	pub roll: Option<u32>,
+	pub text: String,
+}
+
+impl Display for TableResult {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self.roll {
+			Some(roll) => write!(f, "{} ({}): {}", self.table, roll, self.text),
+			None => write!(f, "{}: {}", self.table, self.text),
+		}
+	}
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum TableError {
+	Unknown(String),
+	Empty(String),
+	NoRow(String, u32),
+	Loop(Vec<String>),
+}
+
+impl Display for TableError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			TableError::Unknown(name) => write!(f, "Die Tabelle \"{}\" existiert nicht", name),
+			TableError::Empty(name) => write!(f, "Die Tabelle \"{}\" hat keine würfelbaren Zeilen", name),
+			TableError::NoRow(name, roll) => write!(f, "Die Tabelle \"{}\" hat keine Zeile für {}", name, roll),
+			TableError::Loop(names) => write!(f, "Die Tabellen verweisen im Kreis: {}", names.join(" -> ")),
+		}
+	}
+}
+
+impl Error for TableError {}
+
+impl Table {
+	pub fn label(&self) -> String {
+		format!("Tabelle: {}", self.name)
+	}
+
+	/// Rolls on this table and every table referenced by the rolled rows
+	pub fn roll(&self, tables: &[Table], rng: &mut impl Rng) -> Result<Vec<TableResult>, TableError> {
+		let mut results = vec![];
+		let mut path = vec![];
+		self.roll_nested(tables, &mut path, &mut results, rng)?;
+		Ok(results)
+	}
+
+	fn roll_nested(&self, tables: &[Table], path: &mut Vec<String>, results: &mut Vec<TableResult>, rng: &mut impl Rng) -> Result<(), TableError> {
+		path.push(self.name.clone());
+		if path.len() > MAX_DEPTH {
+			return Err(TableError::Loop(path.clone()));
+		}
+
+		let (roll, row) = self.pick(rng)?;
+		if !row.text.is_empty() {
+			results.push(TableResult {
+				table: self.name.clone(),
+				roll,
+				text: row.text.clone(),
+			});
+		}
+		if let Some(name) = &row.table {
+			find(tables, name)
+				.ok_or_else(|| TableError::Unknown(name.clone()))?
+				.roll_nested(tables, path, results, rng)?;
+		}
+		path.pop();
+		Ok(())
+	}
+
+	fn pick(&self, rng: &mut impl Rng) -> Result<(Option<u32>, &Row), TableError> {
+		if let Some(sides) = self.dice {
+			let uniform = Uniform::new_inclusive(1, sides.max(1)).expect("Failed to create uniform distribution for tables");
+			let roll = rng.sample(uniform);
+			return self.rows
+				.iter()
+				.find(|row| row.range.is_some_and(|(min, max)| (min..=max).contains(&roll)))
+				.map(|row| (Some(roll), row))
+				.ok_or_else(|| TableError::NoRow(self.name.clone(), roll));
+		}
+		let distribution = WeightedIndex::new(self.rows.iter().map(|row| row.weight))
+			.map_err(|_| TableError::Empty(self.name.clone()))?;
+		Ok((None, &self.rows[rng.sample(distribution)]))
+	}
+
+	fn defaults() -> Vec<Table> {
+		let row = |text: &str, range: (u32, u32), table: Option<&str>| Row {
+			text: text.to_string(),
+			weight: default_weight(),
+			range: Some(range),
+			table: table.map(str::to_string),
+		};
+		vec![
+			Table {
+				name: "Wetter".to_string(),
+				dice: Some(6),
+				rows: vec![
+					row("Sonnig", (1, 3), None),
+					row("Bewölkt", (4, 4), None),
+					row("Regen", (5, 5), None),
+					row("", (6, 6), Some("Unwetter")),
+				],
+			},
+			Table {
+				name: "Unwetter".to_string(),
+				dice: None,
+				rows: vec![
+					Row { text: "Gewitter".to_string(), weight: 3, range: None, table: None },
+					Row { text: "Hagel".to_string(), weight: 2, range: None, table: None },
+					Row { text: "Sturm".to_string(), weight: 1, range: None, table: None },
+				],
+			},
+		]
+	}
+
+	fn save(&self, directory: &Path) -> Result<(), Box<dyn Error>> {
+		let writer = BufWriter::new(File::create(directory.join(format!("{}.yaml", self.name)))?);
+		serde_yaml::to_writer(writer, self)?;
+		Ok(())
+	}
+}
+
+pub fn find<'a>(tables: &'a [Table], name: &str) -> Option<&'a Table> {
+	tables.iter()
+		.find(|table| table.name == name)
+		.or_else(|| tables.iter().find(|table| table.name.eq_ignore_ascii_case(name)))
+}
+
+/**
+Loads every `.yaml` file in the `tables` folder, sorted by name.
+Creates the folder with example tables if it does not exist. Broken files are reported and skipped
+ */
+pub fn load_all() -> Vec<Table> {
+	let directory = settings_path(TABLE_DIR);
+	if !directory.exists() {
+		if let Err(err) = create_dir_all(&directory) {
+			edbgprintln!("{}", err);
+			return vec![];
+		}
+		for table in Table::defaults() {
+			if let Err(err) = table.save(&directory) {
+				edbgprintln!("{}", err);
+			}
+		}
+		edbgprintln!("Neue Tabellen wurden erzeugt");
+	}
+
+	let entries = match read_dir(&directory) {
+		Ok(entries) => entries,
+		Err(err) => {
+			edbgprintln!("{}", err);
+			return vec![];
+		}
+	};
+	let mut tables: Vec<Table> = entries
+		.filter_map(|entry| entry.ok())
+		.map(|entry| entry.path())
+		.filter(|path| path.extension().is_some_and(|extension| extension == "yaml"))
+		.filter_map(|path| {
+			let table = File::open(&path)
+				.map_err(|err| err.to_string())
+				.and_then(|file| serde_yaml::from_reader::<_, Table>(BufReader::new(file)).map_err(|err| err.to_string()));
+			match table {
+				Ok(mut table) => {
+					if table.name.is_empty() {
+						table.name = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+					}
+					Some(table)
+				},
+				Err(err) => {
+					edbgprintln!("{}: {}", path.display(), err);
+					None
+				},
+			}
+		})
+		.collect();
+	tables.sort_by(|a, b| a.name.cmp(&b.name));
+	tables
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::table::{Table, TableError};
+	use rand::rngs::StdRng;
+	use rand::SeedableRng;
+
+	#[test]
+	fn test_parse_rows() {
+		let yaml = "
+dice: 6
+rows:
+  - text: Sonnig
+    range: 1-3
+  - text: Regen
+    range: 4
+  - range: 5–6
+    table: Unwetter
+";
+		let table: Table = serde_yaml::from_str(yaml).unwrap();
+		assert_eq!(table.rows[0].range, Some((1, 3)));
+		assert_eq!(table.rows[1].range, Some((4, 4)));
+		assert_eq!(table.rows[2].range, Some((5, 6)));
+		assert_eq!(table.rows[2].table.as_deref(), Some("Unwetter"));
+		assert!(serde_yaml::from_str::<Table>("rows:\n  - text: Regen\n    range: 3-1\n").is_err());
+	}
+
+	#[test]
+	fn test_nested_roll() {
+		let tables = Table::defaults();
+		let mut rng = StdRng::seed_from_u64(0);
+		let mut nested = false;
+		for _ in 0..100 {
+			let results = tables[0].roll(&tables, &mut rng).unwrap();
+			assert_eq!(results.len(), 1);
+			nested |= results[0].table == "Unwetter";
+		}
+		assert!(nested);
+
+		let looping: Table = serde_yaml::from_str("name: Kreis\nrows:\n  - table: Kreis\n").unwrap();
+		assert!(matches!(looping.roll(std::slice::from_ref(&looping), &mut rng), Err(TableError::Loop(_))));
+	}
+}
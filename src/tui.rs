@@ -0,0 +1,395 @@
+use crate::color::get_color;
+use crate::commands::{self, decay::{self, DecayReport}};
+use crate::history::{Entry, History, Kind};
+use crate::repl::{self, Repl};
+use crate::rng::Seeds;
+use crate::statistics::Statistics;
+use ansi_term::Colour;
+use clap::ArgMatches;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::io;
+
+/// Commands of the REPL which only print and have a panel of their own or would break the screen
+const UNSUPPORTED: [&str; 3] = ["disadvantage", "history", "stats"];
+
+/// Colors of the colored dice are the same as in the menu
+fn to_color(colour: Colour) -> Color {
+	match colour {
+		Colour::Black => Color::Black,
+		Colour::Red => Color::Red,
+		Colour::Green => Color::Green,
+		Colour::Yellow => Color::Yellow,
+		Colour::Blue => Color::Blue,
+		Colour::Purple => Color::Magenta,
+		Colour::Cyan => Color::Cyan,
+		Colour::White => Color::White,
+		Colour::Fixed(index) => Color::Indexed(index),
+		Colour::RGB(r, g, b) => Color::Rgb(r, g, b),
+	}
+}
+
+/// Byte position of the `index`th char
+fn byte_index(input: &str, index: usize) -> usize {
+	input.char_indices().nth(index).map(|(byte, _)| byte).unwrap_or(input.len())
+}
+
+/// Longest start all `words` have in common
+fn common_prefix(words: &[String]) -> String {
+	let Some(first) = words.first() else {
+		return String::new();
+	};
+	let mut prefix = first.as_str();
+	for word in &words[1..] {
+		let length = prefix.chars()
+			.zip(word.chars())
+			.take_while(|(a, b)| a == b)
+			.map(|(char, _)| char.len_utf8())
+			.sum();
+		prefix = &prefix[..length];
+	}
+	prefix.to_string()
+}
+
+/**
+Full-screen dashboard, uses the command language of the REPL and rolls with the same engines
+ */
+struct Dashboard<'a> {
+	repl: &'a Repl<'a>,
+	input: String,
+	/// In chars
+	cursor: usize,
+	/// Entered commands of this dashboard for up and down
+	entered: Vec<String>,
+	browsing: Option<usize>,
+	/// Lines the results log is scrolled up
+	scroll: u16,
+	message: String,
+	error: bool,
+}
+
+impl Dashboard<'_> {
+	fn inform(&mut self, message: impl Into<String>) {
+		self.message = message.into();
+		self.error = false;
+	}
+
+	fn fail(&mut self, message: impl Into<String>) {
+		self.message = message.into();
+		self.error = true;
+	}
+
+	/// Handles a key, returns whether the dashboard should end
+	fn handle(&mut self, key: KeyEvent, history: &mut History, seeds: &mut Seeds) -> bool {
+		match key.code {
+			KeyCode::Esc => return true,
+			KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return true,
+			KeyCode::Enter => {
+				let line = std::mem::take(&mut self.input);
+				self.cursor = 0;
+				self.browsing = None;
+				if line.trim().is_empty() {
+					return false;
+				}
+				self.entered.push(line.clone());
+				return self.execute(&line, history, seeds);
+			},
+			KeyCode::Char(char) => {
+				let index = byte_index(&self.input, self.cursor);
+				self.input.insert(index, char);
+				self.cursor += 1;
+			},
+			KeyCode::Backspace if self.cursor > 0 => {
+				self.cursor -= 1;
+				let index = byte_index(&self.input, self.cursor);
+				self.input.remove(index);
+			},
+			KeyCode::Delete if self.cursor < self.input.chars().count() => {
+				let index = byte_index(&self.input, self.cursor);
+				self.input.remove(index);
+			},
+			KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+			KeyCode::Right => self.cursor = (self.cursor + 1).min(self.input.chars().count()),
+			KeyCode::Home => self.cursor = 0,
+			KeyCode::End => self.cursor = self.input.chars().count(),
+			KeyCode::Up | KeyCode::Down => {
+				let browsing = match (key.code, self.browsing) {
+					(KeyCode::Up, None) => self.entered.len().checked_sub(1),
+					(KeyCode::Up, Some(index)) => Some(index.saturating_sub(1)),
+					(_, Some(index)) if index + 1 < self.entered.len() => Some(index + 1),
+					_ => None,
+				};
+				self.browsing = browsing;
+				self.input = browsing.map(|index| self.entered[index].clone()).unwrap_or_default();
+				self.cursor = self.input.chars().count();
+			},
+			KeyCode::PageUp => self.scroll = self.scroll.saturating_add(10),
+			KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(10),
+			KeyCode::Tab => self.complete(),
+			_ => {},
+		}
+		false
+	}
+
+	fn complete(&mut self) {
+		let end = byte_index(&self.input, self.cursor);
+		let (start, pairs) = self.repl.candidates(&self.input[..end]);
+		let replacements: Vec<String> = pairs.iter().map(|pair| pair.replacement.clone()).collect();
+		let completed = common_prefix(&replacements);
+		if completed.len() > end - start {
+			self.input.replace_range(start..end, &completed);
+			self.cursor = self.input[..start + completed.len()].chars().count();
+		}
+		match pairs.len() {
+			0 | 1 => self.inform(""),
+			_ => {
+				let displays: Vec<&str> = pairs.iter().map(|pair| pair.display.as_str()).collect();
+				self.inform(displays.join(", "));
+			},
+		}
+	}
+
+	/// Runs one line, returns whether the dashboard should end
+	fn execute(&mut self, line: &str, history: &mut History, seeds: &mut Seeds) -> bool {
+		let matches = match repl::split(line).and_then(|words| repl::command().try_get_matches_from(words).map_err(|err| err.to_string())) {
+			Ok(matches) => matches,
+			Err(err) => {
+				// Only the first line, the usage does not fit
+				self.fail(err.lines().next().unwrap_or_default().trim_start_matches("error: "));
+				return false;
+			},
+		};
+		let Some((name, matches)) = matches.subcommand() else {
+			return false;
+		};
+		self.scroll = 0;
+		let result = match name {
+			"exit" => return true,
+			"help" => Ok("Befehle: r 3d6, c 2r1s, c --value 20, crit 5, spell kampf, t <Tabelle>, decay --start \"10;10;0\" --op <Operation>, undo, exit. Tab ergänzt, Bild auf/ab blättert".to_string()),
+			"undo" => history.undo().map(|entry| format!("Rückgängig gemacht: {}: {}", entry.kind, entry.input)),
+			name if UNSUPPORTED.contains(&name) => Err(format!("{} gibt es nur in der Eingabezeile und im Menü", name)),
+			name => self.roll(name, matches, history, seeds),
+		};
+		match result {
+			Ok(message) => self.inform(message),
+			Err(err) => self.fail(err),
+		}
+		false
+	}
+
+	/// Rolls like the subcommand `name` and records the roll, the log shows it from the history
+	fn roll(&self, name: &str, matches: &ArgMatches, history: &mut History, seeds: &mut Seeds) -> Result<String, String> {
+		let repl = self.repl;
+		// A command which can not roll does not get a generator
+		repl.validate(name, matches)?;
+		let mut rng = history.next_rng(seeds);
+		match name {
+			"roll" => commands::roll::evaluate(matches, repl.normal_dices, repl.old_style, &mut rng)?.1.record(history),
+			"colored" => commands::colored::evaluate(matches, repl.colored_dice, &mut rng)?.record(history),
+			"crit" => commands::crit::evaluate(matches, repl.crits, &mut rng).record(history),
+			"spell" => {
+				let rolled = commands::spell::evaluate(matches, repl.spells, &mut rng)?;
+				rolled.record(history);
+				if let Some(shortage) = commands::spell::shortage(matches, &rolled) {
+					return Err(shortage);
+				}
+			},
+			"table" => {
+				let table = commands::table::find(matches, repl.tables)?;
+				commands::table::evaluate(table, repl.tables, &mut rng)?.record(history);
+			},
+			"decay" => {
+				let report = decay::decay(matches, repl.operations, repl.classifications, &mut rng)?;
+				decay::record(matches, &report, history);
+				if let Some(error) = report.error {
+					return Err(format!("Nicht möglich! {}", error));
+				}
+			},
+			name => return Err(format!("Unbekannter Befehl: {}", name)),
+		}
+		Ok(String::new())
+	}
+
+	fn draw(&self, frame: &mut Frame, history: &History) {
+		let session = history.session();
+		let [main, input, message] = Layout::vertical([Constraint::Min(0), Constraint::Length(3), Constraint::Length(1)]).areas(frame.area());
+		let [log, side] = Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(main);
+		let dice_height = self.repl.colored_dice.dices.len() as u16 + 2;
+		let [statistics, dice, decay] = Layout::vertical([Constraint::Fill(1), Constraint::Length(dice_height), Constraint::Fill(1)]).areas(side);
+
+		self.draw_log(frame, log, &session);
+
+		let entries: Vec<&Entry> = session.iter().collect();
+		let statistics_text = Statistics::new(&entries, self.repl.colored_dice).to_string();
+		frame.render_widget(
+			Paragraph::new(statistics_text).wrap(Wrap { trim: false }).block(Block::bordered().title(" Statistik der Sitzung ")),
+			statistics,
+		);
+
+		let lines: Vec<Line> = self.repl.colored_dice.dices.iter()
+			.map(|dice| {
+				let style = get_color(&dice.color).map(|colour| Style::new().fg(to_color(colour))).unwrap_or_default();
+				Line::from(vec![
+					Span::styled(format!("{} {}", dice.short, dice.long), style.add_modifier(Modifier::BOLD)),
+					Span::raw(format!(" Seiten {:?}, Wert {}", dice.sites, dice.value)),
+				])
+			})
+			.collect();
+		frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" Farbige Würfel ")), dice);
+
+		frame.render_widget(
+			Paragraph::new(self.decay_text(&session)).wrap(Wrap { trim: false }).block(Block::bordered().title(" Zerfallsreihe ")),
+			decay,
+		);
+
+		frame.render_widget(Paragraph::new(self.input.as_str()).block(Block::bordered().title(" Eingabe, help zeigt die Befehle ")), input);
+		frame.set_cursor_position((input.x + 1 + self.cursor as u16, input.y + 1));
+		let style = if self.error { Style::new().fg(Color::Red) } else { Style::new() };
+		frame.render_widget(Paragraph::new(self.message.as_str()).style(style), message);
+	}
+
+	/// Newest roll at the bottom
+	fn draw_log(&self, frame: &mut Frame, area: Rect, session: &[Entry]) {
+		let mut lines = vec![];
+		for (index, entry) in session.iter().enumerate() {
+			lines.push(Line::styled(format!("{}. {}: {}", index + 1, entry.kind, entry.input), Style::new().add_modifier(Modifier::BOLD)));
+			lines.extend(entry.text.lines().map(|line| Line::raw(line.to_string())));
+		}
+		let height = area.height.saturating_sub(2);
+		let bottom = (lines.len() as u16).saturating_sub(height);
+		let scroll = bottom.saturating_sub(self.scroll);
+		frame.render_widget(Paragraph::new(Text::from(lines)).scroll((scroll, 0)).block(Block::bordered().title(" Würfe ")), area);
+	}
+
+	/// State of the last decay series of the session
+	fn decay_text(&self, session: &[Entry]) -> Text<'static> {
+		let report = session.iter()
+			.rev()
+			.filter(|entry| entry.kind == Kind::Decay)
+			.find_map(|entry| serde_json::from_value::<DecayReport>(entry.result.clone()).ok());
+		let Some(report) = report else {
+			return Text::raw("Noch keine Zerfallsreihe, z.B. decay --start \"10;10;0\" --op <Operation>");
+		};
+		let mut lines = vec![Line::raw(format!("Start: {}", report.start.get_description(self.repl.classifications)))];
+		for step in &report.steps {
+			let (mark, color) = if step.success { ("+", Color::Green) } else { ("-", Color::Red) };
+			lines.push(Line::styled(format!("{} {}", mark, step.operation), Style::new().fg(color)));
+		}
+		lines.extend(report.state.to_string().lines().map(|line| Line::raw(line.to_string())));
+		lines.push(Line::styled(format!("Ergebnis: {}", report.description), Style::new().add_modifier(Modifier::BOLD)));
+		Text::from(lines)
+	}
+}
+
+/**
+Runs the dashboard until Esc or `exit`, the terminal is restored even after an error
+ */
+pub fn run(repl: &Repl, history: &mut History, seeds: &mut Seeds) -> io::Result<()> {
+	let mut dashboard = Dashboard {
+		repl,
+		input: String::new(),
+		cursor: 0,
+		entered: vec![],
+		browsing: None,
+		scroll: 0,
+		message: String::new(),
+		error: false,
+	};
+	let mut terminal = ratatui::try_init()?;
+	let result = run_loop(&mut terminal, &mut dashboard, history, seeds);
+	ratatui::restore();
+	result
+}
+
+fn run_loop(terminal: &mut DefaultTerminal, dashboard: &mut Dashboard, history: &mut History, seeds: &mut Seeds) -> io::Result<()> {
+	loop {
+		terminal.draw(|frame| dashboard.draw(frame, history))?;
+		if let Event::Key(key) = event::read()?
+			&& key.kind == KeyEventKind::Press
+			&& dashboard.handle(key, history, seeds)
+		{
+			return Ok(());
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::decay_series::{Classification, Operation};
+	use crate::dice::colored_dice::ColoredDices;
+	use crate::dice::crit_dice::CritDices;
+	use crate::dice::normal_dice::Dices;
+	use crate::history::{History, Kind};
+	use crate::output::Format;
+	use crate::repl::Repl;
+	use crate::rng::{Backend, Seed, Seeds};
+	use crate::spell::Spells;
+	use crate::tui::{common_prefix, Dashboard};
+
+	#[test]
+	fn test_common_prefix() {
+		assert_eq!(common_prefix(&["3d10".to_string(), "3d100".to_string()]), "3d10");
+		assert_eq!(common_prefix(&["Würfel".to_string(), "Wurf".to_string()]), "W");
+		assert_eq!(common_prefix(&[]), "");
+	}
+
+	#[test]
+	fn test_execute() {
+		let colored_dice = ColoredDices::default();
+		let spells = Spells::defaults();
+		let repl = Repl {
+			colored_dice: &colored_dice,
+			normal_dices: &Dices::default(),
+			crits: &CritDices::default(),
+			spells: &spells,
+			disadvantages: &[],
+			tables: &[],
+			operations: &Vec::<Operation>::new(),
+			classifications: &Vec::<Classification>::new(),
+			old_style: false,
+			no_summary: false,
+			format: Format::Text,
+		};
+		let mut dashboard = Dashboard {
+			repl: &repl,
+			input: String::new(),
+			cursor: 0,
+			entered: vec![],
+			browsing: None,
+			scroll: 0,
+			message: String::new(),
+			error: false,
+		};
+		let mut history = History::in_memory();
+		let mut seeds = Seeds::new(Backend::Std, Some(Seed::Short(0)), false);
+
+		// A failed command leaves the chain of sub-seeds unchanged
+		assert!(!dashboard.execute("t Beute", &mut history, &mut seeds));
+		assert!(dashboard.error);
+		assert_eq!(seeds.last(), None);
+		assert!(!dashboard.execute("r 3d6", &mut history, &mut seeds));
+		let session = history.session();
+		assert_eq!(session.len(), 1);
+		assert_eq!((session[0].kind, session[0].input.as_str()), (Kind::Roll, "3d6"));
+		let mut fresh = Seeds::new(Backend::Std, Some(Seed::Short(0)), false);
+		fresh.next_rng();
+		assert_eq!(session[0].seed, fresh.last());
+
+		assert!(!dashboard.execute("crit 3", &mut history, &mut seeds));
+		assert_eq!(history.session().last().map(|entry| entry.kind), Some(Kind::Crit));
+		assert!(!dashboard.execute("undo", &mut history, &mut seeds));
+		assert!(!dashboard.error);
+		assert_eq!(history.session().len(), 1);
+
+		assert!(!dashboard.execute("r 3d7", &mut history, &mut seeds));
+		assert!(dashboard.error);
+		assert!(!dashboard.execute("t Beute", &mut history, &mut seeds));
+		assert!(dashboard.error);
+		assert_eq!(history.session().len(), 1);
+		assert!(dashboard.execute("exit", &mut history, &mut seeds));
+	}
+}